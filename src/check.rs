@@ -0,0 +1,241 @@
+//! Static validation of a project's scripts, without running the game.
+use crate::{
+    errors::format_wren_error,
+    io::{scan_imports, scan_variables, LoadedModule, ModuleOrigin, ModuleSources},
+};
+use rust_wren::{prelude::*, WrenError};
+use smol_str::SmolStr;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// Optional modules built into the Wren VM, which the module loader never sees.
+const VM_MODULES: &[&str] = &["meta", "random"];
+
+/// Problem found in a script.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    /// File, and line when known, that the problem is in.
+    pub location: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn at_line(origin: &ModuleOrigin, line: usize, message: String) -> Self {
+        Self {
+            location: format!("{}:{}", origin, line),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Finds the modules reachable from the entry point, using the given
+/// function to load imported modules. Each module comes after the
+/// modules it imports, unless the imports are cyclic.
+///
+/// Imported modules that can't be loaded are reported.
+pub fn collect_modules<F>(entry: LoadedModule, load: F) -> (Vec<LoadedModule>, Vec<Diagnostic>)
+where
+    F: FnMut(&str) -> Option<LoadedModule>,
+{
+    let mut collector = Collector {
+        load,
+        visited: HashSet::new(),
+        modules: vec![],
+        diagnostics: vec![],
+    };
+    collector.visited.insert(entry.name.clone());
+    collector.visit(entry);
+
+    (collector.modules, collector.diagnostics)
+}
+
+struct Collector<F> {
+    load: F,
+    visited: HashSet<SmolStr>,
+    modules: Vec<LoadedModule>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<F> Collector<F>
+where
+    F: FnMut(&str) -> Option<LoadedModule>,
+{
+    fn visit(&mut self, module: LoadedModule) {
+        for import in scan_imports(&module.source) {
+            let name = SmolStr::from(import.name.as_str());
+            if VM_MODULES.contains(&import.name.as_str()) || !self.visited.insert(name) {
+                continue;
+            }
+
+            match (self.load)(&import.name) {
+                Some(imported) => self.visit(imported),
+                None => self.diagnostics.push(Diagnostic::at_line(
+                    &module.origin,
+                    import.line(&module.source),
+                    format!("module '{}' was not found", import.name),
+                )),
+            }
+        }
+
+        self.modules.push(module);
+    }
+}
+
+/// Reports imported variables that the imported module doesn't declare.
+pub fn check_imports(modules: &[LoadedModule]) -> Vec<Diagnostic> {
+    let variables: HashMap<&str, HashSet<String>> = modules
+        .iter()
+        .map(|module| {
+            (
+                module.name.as_str(),
+                scan_variables(&module.source).into_iter().collect(),
+            )
+        })
+        .collect();
+    let mut diagnostics = vec![];
+
+    for module in modules.iter().filter(|module| module.origin != ModuleOrigin::Builtin) {
+        for import in scan_imports(&module.source) {
+            let declared = match variables.get(import.name.as_str()) {
+                Some(declared) => declared,
+                None => continue,
+            };
+
+            for variable in import.variables.iter().filter(|variable| !declared.contains(*variable)) {
+                diagnostics.push(Diagnostic::at_line(
+                    &module.origin,
+                    import.line(&module.source),
+                    format!("module '{}' doesn't export '{}'", import.name, variable),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Interprets the project's modules one at a time, in the order they
+/// were collected, so the compile errors of every module are reported
+/// even when a module it imports fails. Builtin modules are left to
+/// the VM's module loader.
+///
+/// The files that the loader read are passed in, because modules in
+/// an import cycle may already have been loaded by an import.
+pub fn compile_modules(vm: &mut WrenVm, modules: &[LoadedModule], loaded: &ModuleSources) -> Vec<Diagnostic> {
+    let origins: HashMap<&str, &ModuleOrigin> = modules
+        .iter()
+        .map(|module| (module.name.as_str(), &module.origin))
+        .collect();
+    let mut failed: HashSet<&str> = HashSet::new();
+    let mut diagnostics = vec![];
+
+    for module in modules {
+        match &module.origin {
+            ModuleOrigin::Builtin => continue,
+            ModuleOrigin::File(path) if loaded.paths().contains(path) => continue,
+            _ => {}
+        }
+
+        match vm.interpret(&module.name, &module.source) {
+            Ok(_) => {}
+            Err(WrenError::CompileError(errors)) => {
+                failed.insert(module.name.as_str());
+
+                for error in &errors {
+                    let location = match origins.get(error.module.as_str()) {
+                        Some(origin) => format!("{}:{}", origin, error.line),
+                        None => format!("{} line {}", error.module, error.line),
+                    };
+                    diagnostics.push(Diagnostic {
+                        location,
+                        message: error.message.clone(),
+                    });
+                }
+            }
+            Err(err) => {
+                failed.insert(module.name.as_str());
+
+                // Importing a module that failed is a runtime error too,
+                // which would only repeat the error.
+                let imports_failed = scan_imports(&module.source)
+                    .iter()
+                    .any(|import| failed.contains(import.name.as_str()));
+                if !imports_failed {
+                    diagnostics.push(Diagnostic {
+                        location: module.origin.to_string(),
+                        message: format_wren_error(&err),
+                    });
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn module(name: &str, source: &str) -> LoadedModule {
+        LoadedModule {
+            name: name.into(),
+            source: source.to_string(),
+            origin: ModuleOrigin::File(PathBuf::from(format!("{}.wren", name))),
+        }
+    }
+
+    #[test]
+    fn test_collect_modules() {
+        let entry = module(
+            "main",
+            "import \"player\" for Player\nimport \"missing\" for Nothing\nimport \"random\" for Random",
+        );
+        let (modules, diagnostics) = collect_modules(entry, |name| match name {
+            "player" => Some(module(
+                "player",
+                "import \"util\" for Util\nimport \"main\"\nclass Player {}",
+            )),
+            "util" => Some(module("util", "class Util {}")),
+            _ => None,
+        });
+
+        let names: Vec<&str> = modules.iter().map(|module| module.name.as_str()).collect();
+        assert_eq!(names, vec!["util", "player", "main"]);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                location: "main.wren:2".to_string(),
+                message: "module 'missing' was not found".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_imports() {
+        let modules = vec![
+            module(
+                "util",
+                "import \"gers.math\" for Vector2f\nclass Util {}\nvar Speed = 1",
+            ),
+            module("main", "\nimport \"util\" for Util, Speed, Vector2f, Helper"),
+        ];
+
+        assert_eq!(
+            check_imports(&modules),
+            vec![Diagnostic {
+                location: "main.wren:2".to_string(),
+                message: "module 'util' doesn't export 'Helper'".to_string(),
+            }]
+        );
+    }
+}
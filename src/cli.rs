@@ -0,0 +1,529 @@
+//! Command line arguments.
+use crate::{
+    errors::{GersError, GersResult},
+    manifest::{LogFormat, LogLevel},
+};
+use std::str::FromStr;
+
+/// Subcommand selected by the first argument.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Run a game from an entry point script. This is the
+    /// default when no subcommand is given.
+    Run(RunArgs),
+    /// Interactive prompt for evaluating Wren code.
+    Repl,
+    /// Run the test scripts found in the module roots.
+    Test(TestArgs),
+    /// Bundle the project's scripts and assets into an archive.
+    Pack(PackArgs),
+    /// Compile the scripts reachable from the entry point, without running the game.
+    Check(CheckArgs),
+}
+
+impl Command {
+    /// Parse the given arguments, excluding the executable name.
+    pub fn parse<I>(args: I) -> GersResult<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter().peekable();
+
+        match args.peek().map(|arg| arg.as_str()) {
+            Some("repl") => {
+                args.next();
+                expect_end(args)?;
+                Ok(Command::Repl)
+            }
+            Some("test") => {
+                args.next();
+                TestArgs::parse(args).map(Command::Test)
+            }
+            Some("pack") => {
+                args.next();
+                PackArgs::parse(args).map(Command::Pack)
+            }
+            Some("check") => {
+                args.next();
+                CheckArgs::parse(args).map(Command::Check)
+            }
+            _ => RunArgs::parse(args).map(Command::Run),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunArgs {
+    /// Path to the entry point script. When `None` the
+    /// entry point is taken from the project manifest.
+    pub entry: Option<String>,
+    /// Run the game loop without creating a window or OpenGL context.
+    pub headless: bool,
+    /// Number of frames to run when headless. When `None` the
+    /// game runs until the script calls `Game.quit()`.
+    pub frames: Option<u64>,
+    /// Simulated time, in seconds, that passes each headless frame.
+    pub delta_time: f64,
+    /// Hot reload scripts when they change on disk.
+    pub watch: bool,
+    /// File to record input and frame timing to.
+    pub record: Option<String>,
+    /// File to replay recorded input and frame timing from.
+    pub replay: Option<String>,
+    /// Extra module roots, replacing the ones in the manifest.
+    pub roots: Vec<String>,
+    /// Asset directories, replacing the ones in the manifest.
+    pub assets: Vec<String>,
+    /// Logical window size.
+    pub size: Option<[f64; 2]>,
+    pub title: Option<String>,
+    pub vsync: Option<bool>,
+    pub log_level: Option<LogLevel>,
+    /// Levels of script modules, given as `<module>=<level>`.
+    pub log_modules: Vec<(String, LogLevel)>,
+    pub log_format: Option<LogFormat>,
+    pub log_file: Option<String>,
+    /// File to write a Chrome trace of the frame profiler to.
+    pub profile: Option<String>,
+    /// Number of frames to profile before the trace is written.
+    pub profile_frames: Option<u64>,
+}
+
+impl RunArgs {
+    pub const DEFAULT_DELTA_TIME: f64 = 1.0 / 60.0;
+
+    /// Parse the arguments of the run command.
+    pub fn parse<I>(args: I) -> GersResult<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut entry: Option<String> = None;
+        let mut headless = false;
+        let mut frames = None;
+        let mut delta_time = Self::DEFAULT_DELTA_TIME;
+        let mut watch = false;
+        let mut record = None;
+        let mut replay = None;
+        let mut roots = vec![];
+        let mut assets = vec![];
+        let mut size = None;
+        let mut title = None;
+        let mut vsync = None;
+        let mut log_level = None;
+        let mut log_modules = vec![];
+        let mut log_format = None;
+        let mut log_file = None;
+        let mut profile = None;
+        let mut profile_frames = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--frames" => frames = Some(parse_value(&arg, args.next())?),
+                "--delta-time" => delta_time = parse_delta_time(&arg, args.next())?,
+                "--watch" => watch = true,
+                "--record" => record = Some(parse_value(&arg, args.next())?),
+                "--replay" => replay = Some(parse_value(&arg, args.next())?),
+                "--root" => roots.push(parse_value(&arg, args.next())?),
+                "--assets" => assets.push(parse_value(&arg, args.next())?),
+                "--size" => size = Some(parse_size(&arg, args.next())?),
+                "--title" => title = Some(parse_value(&arg, args.next())?),
+                "--vsync" => vsync = Some(true),
+                "--no-vsync" => vsync = Some(false),
+                "--log-level" => log_level = Some(parse_value(&arg, args.next())?),
+                "--log-module" => log_modules.push(parse_log_module(&arg, args.next())?),
+                "--log-format" => log_format = Some(parse_value(&arg, args.next())?),
+                "--log-file" => log_file = Some(parse_value(&arg, args.next())?),
+                "--profile" => profile = Some(parse_value(&arg, args.next())?),
+                "--profile-frames" => profile_frames = Some(parse_value(&arg, args.next())?),
+                flag if flag.starts_with("--") => {
+                    return Err(GersError::InvalidCmdArgs(format!("unknown option '{}'", flag)));
+                }
+                _ => {
+                    if let Some(entry) = &entry {
+                        return Err(GersError::InvalidCmdArgs(format!(
+                            "unexpected argument '{}', entry point is already '{}'",
+                            arg, entry
+                        )));
+                    }
+                    entry = Some(arg);
+                }
+            }
+        }
+
+        if record.is_some() && replay.is_some() {
+            return Err(GersError::InvalidCmdArgs(
+                "options '--record' and '--replay' can't be used together".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            entry,
+            headless,
+            frames,
+            delta_time,
+            watch,
+            record,
+            replay,
+            roots,
+            assets,
+            size,
+            title,
+            vsync,
+            log_level,
+            log_modules,
+            log_format,
+            log_file,
+            profile,
+            profile_frames,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TestArgs {
+    /// Directories to search for test scripts. When empty
+    /// the module roots are searched.
+    pub paths: Vec<String>,
+}
+
+impl TestArgs {
+    /// Parse the arguments of the test command.
+    pub fn parse<I>(args: I) -> GersResult<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut paths = vec![];
+
+        for arg in args {
+            if arg.starts_with("--") {
+                return Err(GersError::InvalidCmdArgs(format!("unknown option '{}'", arg)));
+            }
+            paths.push(arg);
+        }
+
+        Ok(Self { paths })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PackArgs {
+    /// Path to the entry point script. When `None` the
+    /// entry point is taken from the project manifest.
+    pub entry: Option<String>,
+    /// Path of the archive to write. Defaults to `game.gpak`
+    /// in the project directory.
+    pub output: Option<String>,
+    /// Compress the archive entries.
+    pub compress: bool,
+}
+
+impl PackArgs {
+    /// Parse the arguments of the pack command.
+    pub fn parse<I>(args: I) -> GersResult<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut entry: Option<String> = None;
+        let mut output = None;
+        let mut compress = false;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--output" => output = Some(parse_value(&arg, args.next())?),
+                "--compress" => compress = true,
+                flag if flag.starts_with("--") => {
+                    return Err(GersError::InvalidCmdArgs(format!("unknown option '{}'", flag)));
+                }
+                _ => {
+                    if let Some(entry) = &entry {
+                        return Err(GersError::InvalidCmdArgs(format!(
+                            "unexpected argument '{}', entry point is already '{}'",
+                            arg, entry
+                        )));
+                    }
+                    entry = Some(arg);
+                }
+            }
+        }
+
+        Ok(Self {
+            entry,
+            output,
+            compress,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CheckArgs {
+    /// Path to the entry point script. When `None` the
+    /// entry point is taken from the project manifest.
+    pub entry: Option<String>,
+}
+
+impl CheckArgs {
+    /// Parse the arguments of the check command.
+    pub fn parse<I>(args: I) -> GersResult<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let entry = match args.next() {
+            Some(flag) if flag.starts_with("--") => {
+                return Err(GersError::InvalidCmdArgs(format!("unknown option '{}'", flag)));
+            }
+            entry => entry,
+        };
+        expect_end(args)?;
+
+        Ok(Self { entry })
+    }
+}
+
+/// Ensure there are no arguments left over.
+fn expect_end<I>(mut args: I) -> GersResult<()>
+where
+    I: Iterator<Item = String>,
+{
+    match args.next() {
+        Some(arg) => Err(GersError::InvalidCmdArgs(format!("unexpected argument '{}'", arg))),
+        None => Ok(()),
+    }
+}
+
+/// Parse the value following an option flag.
+fn parse_value<T>(flag: &str, value: Option<String>) -> GersResult<T>
+where
+    T: FromStr,
+{
+    let value = value.ok_or_else(|| GersError::InvalidCmdArgs(format!("option '{}' expects a value", flag)))?;
+
+    value
+        .parse::<T>()
+        .map_err(|_| GersError::InvalidCmdArgs(format!("invalid value '{}' for option '{}'", value, flag)))
+}
+
+/// Parse a size given as `<width>x<height>`.
+fn parse_size(flag: &str, value: Option<String>) -> GersResult<[f64; 2]> {
+    let value: String = parse_value(flag, value)?;
+    let invalid = || GersError::InvalidCmdArgs(format!("invalid size '{}' for option '{}'", value, flag));
+
+    let mut parts = value.splitn(2, 'x');
+    let width = parts.next().and_then(|w| w.parse().ok()).ok_or_else(invalid)?;
+    let height = parts.next().and_then(|h| h.parse().ok()).ok_or_else(invalid)?;
+
+    Ok([width, height])
+}
+
+/// Parse a frame's delta time in seconds, which can't be negative.
+fn parse_delta_time(flag: &str, value: Option<String>) -> GersResult<f64> {
+    let delta_time: f64 = parse_value(flag, value)?;
+
+    if !delta_time.is_finite() || delta_time < 0.0 {
+        return Err(GersError::InvalidCmdArgs(format!(
+            "invalid delta time '{}' for option '{}'",
+            delta_time, flag
+        )));
+    }
+
+    Ok(delta_time)
+}
+
+/// Parse the level of a module given as `<module>=<level>`.
+fn parse_log_module(flag: &str, value: Option<String>) -> GersResult<(String, LogLevel)> {
+    let value: String = parse_value(flag, value)?;
+    let invalid = || GersError::InvalidCmdArgs(format!("invalid module level '{}' for option '{}'", value, flag));
+
+    let mut parts = value.splitn(2, '=');
+    let module = parts.next().filter(|m| !m.is_empty()).ok_or_else(invalid)?;
+    let level = parts.next().and_then(|l| l.parse().ok()).ok_or_else(invalid)?;
+
+    Ok((module.to_string(), level))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    fn run_args(line: &str) -> RunArgs {
+        match Command::parse(args(line)).unwrap() {
+            Command::Run(run_args) => run_args,
+            command => panic!("expected run command, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let run_args = run_args("examples/basic.wren");
+        assert_eq!(run_args.entry.as_deref(), Some("examples/basic.wren"));
+        assert!(!run_args.headless);
+        assert_eq!(run_args.frames, None);
+        assert!(!run_args.watch);
+    }
+
+    #[test]
+    fn test_parse_watch() {
+        let run_args = run_args("--watch examples/basic.wren");
+        assert!(run_args.watch);
+    }
+
+    #[test]
+    fn test_parse_headless() {
+        let run_args = run_args("--headless --frames 120 --delta-time 0.5 examples/basic.wren");
+        assert_eq!(
+            run_args,
+            RunArgs {
+                entry: Some("examples/basic.wren".to_string()),
+                headless: true,
+                frames: Some(120),
+                delta_time: 0.5,
+                watch: false,
+                record: None,
+                replay: None,
+                roots: vec![],
+                assets: vec![],
+                size: None,
+                title: None,
+                vsync: None,
+                log_level: None,
+                log_modules: vec![],
+                log_format: None,
+                log_file: None,
+                profile: None,
+                profile_frames: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_replay() {
+        let record = run_args("--record game.replay examples/basic.wren");
+        assert_eq!(record.record.as_deref(), Some("game.replay"));
+        assert_eq!(record.replay, None);
+
+        let replay = run_args("--headless --replay game.replay examples/basic.wren");
+        assert_eq!(replay.replay.as_deref(), Some("game.replay"));
+    }
+
+    #[test]
+    fn test_parse_manifest_overrides() {
+        // Entry point is optional when there's a manifest.
+        assert_eq!(run_args("").entry, None);
+
+        let run_args = run_args(
+            "--root lib --root vendor --assets assets --size 800x600 --title Game --no-vsync --log-level warn",
+        );
+        assert_eq!(run_args.roots, vec!["lib".to_string(), "vendor".to_string()]);
+        assert_eq!(run_args.assets, vec!["assets".to_string()]);
+        assert_eq!(run_args.size, Some([800.0, 600.0]));
+        assert_eq!(run_args.title.as_deref(), Some("Game"));
+        assert_eq!(run_args.vsync, Some(false));
+        assert_eq!(run_args.log_level, Some(LogLevel::Warn));
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let run_args = run_args("--profile trace.json --profile-frames 300 examples/basic.wren");
+        assert_eq!(run_args.profile.as_deref(), Some("trace.json"));
+        assert_eq!(run_args.profile_frames, Some(300));
+    }
+
+    #[test]
+    fn test_parse_logging() {
+        let run_args =
+            run_args("--log-module game.ai=trace --log-module ui=error --log-format json --log-file out.log");
+        assert_eq!(
+            run_args.log_modules,
+            vec![
+                ("game.ai".to_string(), LogLevel::Trace),
+                ("ui".to_string(), LogLevel::Error)
+            ]
+        );
+        assert_eq!(run_args.log_format, Some(LogFormat::Json));
+        assert_eq!(run_args.log_file.as_deref(), Some("out.log"));
+    }
+
+    #[test]
+    fn test_parse_repl() {
+        assert_eq!(Command::parse(args("repl")).unwrap(), Command::Repl);
+    }
+
+    #[test]
+    fn test_parse_test() {
+        assert_eq!(
+            Command::parse(args("test")).unwrap(),
+            Command::Test(TestArgs { paths: vec![] })
+        );
+        assert_eq!(
+            Command::parse(args("test examples tests")).unwrap(),
+            Command::Test(TestArgs {
+                paths: vec!["examples".to_string(), "tests".to_string()]
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_pack() {
+        assert_eq!(
+            Command::parse(args("pack")).unwrap(),
+            Command::Pack(PackArgs {
+                entry: None,
+                output: None,
+                compress: false,
+            })
+        );
+        assert_eq!(
+            Command::parse(args("pack --compress --output dist/game.gpak src/main.wren")).unwrap(),
+            Command::Pack(PackArgs {
+                entry: Some("src/main.wren".to_string()),
+                output: Some("dist/game.gpak".to_string()),
+                compress: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_check() {
+        assert_eq!(
+            Command::parse(args("check")).unwrap(),
+            Command::Check(CheckArgs { entry: None })
+        );
+        assert_eq!(
+            Command::parse(args("check src/main.wren")).unwrap(),
+            Command::Check(CheckArgs {
+                entry: Some("src/main.wren".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_err() {
+        assert!(Command::parse(args("--size 800 a.wren")).is_err());
+        assert!(Command::parse(args("--size 800xabc a.wren")).is_err());
+        assert!(Command::parse(args("--log-level loud a.wren")).is_err());
+        assert!(Command::parse(args("--log-module game a.wren")).is_err());
+        assert!(Command::parse(args("--log-module =info a.wren")).is_err());
+        assert!(Command::parse(args("--log-format xml a.wren")).is_err());
+        assert!(Command::parse(args("--delta-time -0.5 a.wren")).is_err());
+        assert!(Command::parse(args("--delta-time NaN a.wren")).is_err());
+        assert!(Command::parse(args("--delta-time inf a.wren")).is_err());
+        assert!(Command::parse(args("--frames examples/basic.wren")).is_err());
+        assert!(Command::parse(args("--frames")).is_err());
+        assert!(Command::parse(args("--unknown examples/basic.wren")).is_err());
+        assert!(Command::parse(args("a.wren b.wren")).is_err());
+        assert!(Command::parse(args("--record a.replay --replay b.replay a.wren")).is_err());
+        assert!(Command::parse(args("repl examples/basic.wren")).is_err());
+        assert!(Command::parse(args("test --unknown")).is_err());
+        assert!(Command::parse(args("pack --output")).is_err());
+        assert!(Command::parse(args("pack a.wren b.wren")).is_err());
+        assert!(Command::parse(args("check a.wren b.wren")).is_err());
+        assert!(Command::parse(args("check --headless")).is_err());
+    }
+}
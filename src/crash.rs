@@ -0,0 +1,264 @@
+//! Crash reports.
+//!
+//! When the game dies from a panic, or from an error the game loop
+//! can't recover from, a report is written to the crash directory, so
+//! testers can attach a single file to a bug. Besides the error, it
+//! holds the engine state needed to reproduce it.
+use crate::{
+    errors::{format_wren_error, GersError},
+    graphics::OpenGlInfo,
+    logging::LogHistory,
+    util::crate_version,
+    window::WrenWindowConfig,
+};
+use std::{
+    fmt, fs, io, panic,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Directory, relative to the working directory, that reports are written to.
+pub const CRASH_DIR: &str = "crashes";
+
+lazy_static::lazy_static! {
+    static ref CRASH_STATE: Mutex<CrashState> = Mutex::new(CrashState::default());
+}
+
+/// Engine state included in crash reports. The panic hook can't
+/// reach into the game, so the game keeps it up to date.
+#[derive(Default)]
+struct CrashState {
+    /// Reports are only written once a directory is set.
+    dir: Option<PathBuf>,
+    log_history: Option<LogHistory>,
+    opengl_info: Option<String>,
+    window_conf: Option<String>,
+}
+
+fn crash_state() -> MutexGuard<'static, CrashState> {
+    // A report is still wanted when a panic poisoned the lock.
+    CRASH_STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Enables crash reports, and installs a panic hook that writes one
+/// after the default hook has printed the panic.
+pub fn install_crash_reports(dir: PathBuf, log_history: LogHistory) {
+    {
+        let mut state = crash_state();
+        state.dir = Some(dir);
+        state.log_history = Some(log_history);
+    }
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        // The panic may have happened while logging, so the
+        // outcome is printed instead.
+        match write_crash_report(info.to_string()) {
+            Ok(Some(path)) => eprintln!("Crash report written to {}", path.display()),
+            Ok(None) => {}
+            Err(err) => eprintln!("Failed to write crash report: {}", err),
+        }
+    }));
+}
+
+pub fn set_opengl_info(opengl_info: Option<&OpenGlInfo>) {
+    crash_state().opengl_info = opengl_info.map(OpenGlInfo::to_string);
+}
+
+pub fn set_window_conf(window_conf: &WrenWindowConfig) {
+    crash_state().window_conf = Some(format!("{:?}", window_conf));
+}
+
+/// Writes a report for an error that ended the game.
+///
+/// Returns the path of the report, or `None` when crash
+/// reports aren't enabled.
+pub fn report_error(err: &GersError) -> io::Result<Option<PathBuf>> {
+    let message = match err {
+        GersError::Wren(wren_err) => format_wren_error(wren_err),
+        _ => err.to_string(),
+    };
+
+    write_crash_report(message)
+}
+
+fn write_crash_report(error: String) -> io::Result<Option<PathBuf>> {
+    let (dir, report) = {
+        let state = crash_state();
+        let dir = match &state.dir {
+            Some(dir) => dir.clone(),
+            None => return Ok(None),
+        };
+        let report = CrashReport {
+            time: SystemTime::now(),
+            version: crate_version().full,
+            error,
+            opengl_info: state.opengl_info.clone(),
+            window_conf: state.window_conf.clone(),
+            log_lines: state
+                .log_history
+                .as_ref()
+                .and_then(LogHistory::lines)
+                .unwrap_or_default(),
+        };
+        (dir, report)
+    };
+
+    fs::create_dir_all(&dir)?;
+
+    // Crashes within the same second get a numbered suffix.
+    let name = report.file_name();
+    let mut path = dir.join(format!("{}.txt", name));
+    let mut number = 1;
+    while path.exists() {
+        number += 1;
+        path = dir.join(format!("{}-{}.txt", name, number));
+    }
+
+    fs::write(&path, report.to_string())?;
+
+    Ok(Some(path))
+}
+
+pub struct CrashReport {
+    pub time: SystemTime,
+    pub version: String,
+    /// Error message, including the Wren stack trace for script errors.
+    pub error: String,
+    /// Is `None` when the game was running headless, or hadn't
+    /// created its window yet.
+    pub opengl_info: Option<String>,
+    pub window_conf: Option<String>,
+    /// Most recent log lines, oldest first.
+    pub log_lines: Vec<String>,
+}
+
+impl CrashReport {
+    /// File name, without extension, that sorts by time.
+    pub fn file_name(&self) -> String {
+        let (year, month, day, hour, minute, second) = utc_date_time(self.time);
+        format!(
+            "crash-{:04}{:02}{:02}-{:02}{:02}{:02}",
+            year, month, day, hour, minute, second
+        )
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day, hour, minute, second) = utc_date_time(self.time);
+        writeln!(f, "Gers v{} Crash Report", self.version)?;
+        writeln!(
+            f,
+            "Time: {:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year, month, day, hour, minute, second
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "Error:")?;
+        for line in self.error.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        writeln!(f)?;
+
+        match &self.opengl_info {
+            Some(opengl_info) => write!(f, "{}", opengl_info)?,
+            None => writeln!(f, "OpenGL Info: unavailable")?,
+        }
+        writeln!(f)?;
+
+        match &self.window_conf {
+            Some(window_conf) => writeln!(f, "Window Config:\n    {}", window_conf)?,
+            None => writeln!(f, "Window Config: unavailable")?,
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Log (last {} lines):", self.log_lines.len())?;
+        for line in &self.log_lines {
+            writeln!(f, "    {}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a time into the UTC year, month, day, hour, minute and second.
+fn utc_date_time(time: SystemTime) -> (u64, u64, u64, u64, u64, u64) {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch, counting
+    // years from March so the leap day comes last.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+
+    (
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_utc_date_time() {
+        let time = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(utc_date_time(time(0)), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(utc_date_time(time(951_782_400)), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(utc_date_time(time(1_609_459_199)), (2020, 12, 31, 23, 59, 59));
+    }
+
+    #[test]
+    fn test_crash_report() {
+        let report = CrashReport {
+            time: UNIX_EPOCH + Duration::from_secs(1_609_459_199),
+            version: "0.2.0".to_string(),
+            error: "Script Runtime Error: boom\nStack Trace:\n\t1. game update:12".to_string(),
+            opengl_info: None,
+            window_conf: Some("WrenWindowConfig { vsync: true }".to_string()),
+            log_lines: vec!["INFO Loading game".to_string()],
+        };
+
+        assert_eq!(report.file_name(), "crash-20201231-235959");
+        assert_eq!(
+            report.to_string(),
+            "Gers v0.2.0 Crash Report
+Time: 2020-12-31 23:59:59 UTC
+
+Error:
+    Script Runtime Error: boom
+    Stack Trace:
+    \t1. game update:12
+
+OpenGL Info: unavailable
+
+Window Config:
+    WrenWindowConfig { vsync: true }
+
+Log (last 1 lines):
+    INFO Loading game
+"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Builtin components, with systems that run in Rust.
+use crate::graphics::{SpriteBatch, Texture, Transform2D};
+use legion::{IntoQuery, World};
+use smol_str::SmolStr;
+
+/// Linear velocity in units per second, and angular
+/// velocity in degrees per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Velocity {
+    pub x: f32,
+    pub y: f32,
+    pub angular: f32,
+}
+
+/// Textured rectangle drawn at the entity's transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub width: f32,
+    pub height: f32,
+    /// Index into the world's texture table, since textures
+    /// can't be shared between threads as components.
+    pub texture: usize,
+}
+
+/// Names given to an entity by scripts, for grouping in queries.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tags(pub Vec<SmolStr>);
+
+impl Tags {
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|t| t == tag)
+    }
+}
+
+/// Moves and rotates entities by their velocity.
+pub fn integrate_velocity(world: &mut World, delta_time: f32) {
+    let mut query = <(&mut Transform2D, &Velocity)>::query();
+
+    for (transform, velocity) in query.iter_mut(world) {
+        transform.translate(velocity.x * delta_time, velocity.y * delta_time);
+        transform.rotate(velocity.angular * delta_time);
+    }
+}
+
+/// Adds the sprites of all entities to the batch.
+pub fn submit_sprites(world: &World, textures: &[Texture], batch: &mut SpriteBatch) {
+    let mut query = <(&Transform2D, &Sprite)>::query();
+
+    for (transform, sprite) in query.iter(world) {
+        if let Some(texture) = textures.get(sprite.texture) {
+            batch.add(0.0, 0.0, sprite.width, sprite.height, texture, transform.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nalgebra::Point2;
+
+    #[test]
+    fn test_integrate_velocity() {
+        let mut world = World::default();
+        let moving = world.push((
+            Transform2D::default(),
+            Velocity {
+                x: 10.0,
+                y: -4.0,
+                angular: 0.0,
+            },
+        ));
+        let still = world.push((Transform2D::default(),));
+
+        integrate_velocity(&mut world, 0.5);
+
+        let entry = world.entry(moving).unwrap();
+        let transform = entry.get_component::<Transform2D>().unwrap();
+        assert_eq!(transform.position, Point2::new(5.0, -2.0));
+
+        let entry = world.entry(still).unwrap();
+        let transform = entry.get_component::<Transform2D>().unwrap();
+        assert_eq!(transform.position, Point2::new(0.0, 0.0));
+    }
+}
@@ -0,0 +1,30 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug)]
+pub enum EcsError {
+    /// Entity was despawned, or belongs to another world.
+    EntityNotFound,
+
+    /// Entity does not have the builtin component.
+    MissingComponent(&'static str),
+
+    /// Query was given a name that isn't a builtin component.
+    UnknownComponent(String),
+}
+
+impl Error for EcsError {}
+
+impl fmt::Display for EcsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use EcsError as E;
+        match self {
+            E::EntityNotFound => write!(f, "Entity does not exist in the world."),
+            E::MissingComponent(name) => write!(f, "Entity does not have a {} component.", name),
+            E::UnknownComponent(name) => write!(
+                f,
+                "Unknown component {}. Expected one of Transform2D, Velocity or Sprite.",
+                name
+            ),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! Entity component system, backed by `legion`.
+//!
+//! Scripts spawn entities and attach builtin components to them.
+//! Systems that touch many entities each frame, like velocity
+//! integration and sprite submission, run in Rust.
+mod components;
+mod errors;
+mod world;
+
+pub const ECS_MODULE: &str = "gers.ecs";
+pub use self::world::{EntityList, WrenEntity, WrenQuery, WrenWorld};
+
+use crate::io::BuiltinModules;
+use rust_wren::ModuleBuilder;
+
+pub fn register_ecs(builtins: &mut BuiltinModules) {
+    builtins.add(ECS_MODULE, include_str!("ecs.wren"));
+}
+
+pub fn bind_ecs(module: &mut ModuleBuilder) {
+    module.register::<WrenWorld>();
+    module.register::<WrenEntity>();
+    module.register::<WrenQuery>();
+    module.register::<EntityList>();
+}
@@ -0,0 +1,291 @@
+//! Script handles to an entity world.
+use super::{
+    components::{integrate_velocity, submit_sprites, Sprite, Tags, Velocity},
+    errors::EcsError,
+};
+use crate::{
+    collections::{ArrayIterator, OutOfBounds},
+    graphics::{SpriteBatch, Texture, Transform2D},
+};
+use legion::{Entity, IntoQuery, World};
+use rust_wren::{prelude::*, ForeignError};
+use smol_str::SmolStr;
+
+/// Entity world, owning the components of its entities.
+#[wren_class(name = World)]
+pub struct WrenWorld {
+    world: World,
+    /// Textures used by sprite components, referenced by index.
+    textures: Vec<Texture>,
+}
+
+#[wren_methods]
+impl WrenWorld {
+    #[construct]
+    fn new() -> Self {
+        Self {
+            world: World::default(),
+            textures: vec![],
+        }
+    }
+
+    /// Creates an entity with a default transform.
+    fn spawn(&mut self) -> WrenEntity {
+        WrenEntity(self.world.push((Transform2D::default(),)))
+    }
+
+    /// Removes the entity and its components. Returns false
+    /// when the entity didn't exist.
+    fn despawn(&mut self, entity: &WrenCell<WrenEntity>) -> bool {
+        self.world.remove(entity.borrow().0)
+    }
+
+    fn contains(&self, entity: &WrenCell<WrenEntity>) -> bool {
+        self.world.contains(entity.borrow().0)
+    }
+
+    #[method(name = count_)]
+    fn count(&self) -> f64 {
+        self.world.len() as f64
+    }
+
+    /// Returns a copy of the entity's transform.
+    fn transform(&mut self, entity: &WrenCell<WrenEntity>) -> Result<Transform2D, ForeignError> {
+        let entry = self.entry(entity)?;
+        let transform = entry
+            .get_component::<Transform2D>()
+            .map_err(|_| foreign_error!(EcsError::MissingComponent("Transform2D")))?;
+        Ok(transform.clone())
+    }
+
+    #[method(name = setTransform)]
+    fn set_transform(
+        &mut self,
+        entity: &WrenCell<WrenEntity>,
+        transform: &WrenCell<Transform2D>,
+    ) -> Result<(), ForeignError> {
+        let transform = transform.borrow().clone();
+        self.entry(entity)?.add_component(transform);
+        Ok(())
+    }
+
+    #[method(name = setVelocity)]
+    fn set_velocity(
+        &mut self,
+        entity: &WrenCell<WrenEntity>,
+        x: f32,
+        y: f32,
+        angular: f32,
+    ) -> Result<(), ForeignError> {
+        self.entry(entity)?.add_component(Velocity { x, y, angular });
+        Ok(())
+    }
+
+    #[method(name = removeVelocity)]
+    fn remove_velocity(&mut self, entity: &WrenCell<WrenEntity>) -> Result<(), ForeignError> {
+        self.entry(entity)?.remove_component::<Velocity>();
+        Ok(())
+    }
+
+    #[method(name = setSprite)]
+    fn set_sprite(
+        &mut self,
+        entity: &WrenCell<WrenEntity>,
+        texture: &WrenCell<Texture>,
+        width: f32,
+        height: f32,
+    ) -> Result<(), ForeignError> {
+        let texture = self.texture_index(&*texture.borrow());
+        self.entry(entity)?.add_component(Sprite { width, height, texture });
+        Ok(())
+    }
+
+    #[method(name = removeSprite)]
+    fn remove_sprite(&mut self, entity: &WrenCell<WrenEntity>) -> Result<(), ForeignError> {
+        self.entry(entity)?.remove_component::<Sprite>();
+        Ok(())
+    }
+
+    #[method(name = addTag)]
+    fn add_tag(&mut self, entity: &WrenCell<WrenEntity>, tag: &str) -> Result<(), ForeignError> {
+        let mut entry = self.entry(entity)?;
+        let mut tags = entry.get_component::<Tags>().cloned().unwrap_or_default();
+
+        if !tags.contains(tag) {
+            tags.0.push(SmolStr::new(tag));
+            entry.add_component(tags);
+        }
+
+        Ok(())
+    }
+
+    #[method(name = removeTag)]
+    fn remove_tag(&mut self, entity: &WrenCell<WrenEntity>, tag: &str) -> Result<(), ForeignError> {
+        let mut entry = self.entry(entity)?;
+
+        if let Ok(tags) = entry.get_component_mut::<Tags>() {
+            tags.0.retain(|t| t != tag);
+        }
+
+        Ok(())
+    }
+
+    #[method(name = hasTag)]
+    fn has_tag(&mut self, entity: &WrenCell<WrenEntity>, tag: &str) -> Result<bool, ForeignError> {
+        let entry = self.entry(entity)?;
+        Ok(entry
+            .get_component::<Tags>()
+            .map(|tags| tags.contains(tag))
+            .unwrap_or(false))
+    }
+
+    /// Entities that have all the components and tags of the query.
+    fn query(&self, query: &WrenCell<WrenQuery>) -> EntityList {
+        let query = query.borrow();
+        let mut entities = <(Entity, Option<&Velocity>, Option<&Sprite>, Option<&Tags>)>::query();
+
+        let matches = entities
+            .iter(&self.world)
+            .filter(|(_, velocity, sprite, tags)| {
+                (!query.velocity || velocity.is_some())
+                    && (!query.sprite || sprite.is_some())
+                    && query
+                        .tags
+                        .iter()
+                        .all(|tag| tags.map(|tags| tags.contains(tag)).unwrap_or(false))
+            })
+            .map(|(entity, ..)| *entity)
+            .collect();
+
+        EntityList(matches)
+    }
+
+    /// Moves entities with a velocity. The delta time is in seconds.
+    fn integrate(&mut self, delta_time: f32) {
+        integrate_velocity(&mut self.world, delta_time);
+    }
+}
+
+impl WrenWorld {
+    fn entry(&mut self, entity: &WrenCell<WrenEntity>) -> Result<legion::world::Entry, ForeignError> {
+        self.world
+            .entry(entity.borrow().0)
+            .ok_or_else(|| foreign_error!(EcsError::EntityNotFound))
+    }
+
+    /// Index of the texture in the texture table, adding it when needed.
+    fn texture_index(&mut self, texture: &Texture) -> usize {
+        let raw_handle = texture.raw_handle();
+
+        match self.textures.iter().position(|t| t.raw_handle() == raw_handle) {
+            Some(index) => index,
+            None => {
+                self.textures.push(texture.clone());
+                self.textures.len() - 1
+            }
+        }
+    }
+
+    /// Adds the sprites of all entities to the batch.
+    pub fn submit_sprites(&self, batch: &mut SpriteBatch) {
+        submit_sprites(&self.world, &self.textures, batch);
+    }
+}
+
+/// Handle to an entity in a `World`.
+#[wren_class(name = Entity)]
+#[derive(Debug, Clone, Copy)]
+pub struct WrenEntity(pub Entity);
+
+#[wren_methods]
+impl WrenEntity {
+    #[construct]
+    fn new_() -> Self {
+        unimplemented!("Entities must be spawned by a World")
+    }
+
+    #[method(name = toString_)]
+    fn to_string(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// Components and tags that entities must have to match.
+///
+/// Every entity has a `Transform2D`, so it's not part of the filter.
+#[wren_class(name = Query)]
+#[derive(Debug, Default)]
+pub struct WrenQuery {
+    velocity: bool,
+    sprite: bool,
+    tags: Vec<SmolStr>,
+}
+
+#[wren_methods]
+impl WrenQuery {
+    #[construct]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    #[method(name = with_)]
+    fn with(&mut self, component: &str) -> Result<(), ForeignError> {
+        match component {
+            "Transform2D" => {}
+            "Velocity" => self.velocity = true,
+            "Sprite" => self.sprite = true,
+            _ => return Err(foreign_error!(EcsError::UnknownComponent(component.to_string()))),
+        }
+
+        Ok(())
+    }
+
+    #[method(name = withTag_)]
+    fn with_tag(&mut self, tag: &str) {
+        self.tags.push(SmolStr::new(tag));
+    }
+}
+
+/// Entities returned by a query.
+#[wren_class(name = EntityList)]
+pub struct EntityList(Vec<Entity>);
+
+#[wren_methods]
+impl EntityList {
+    #[construct]
+    fn new_() -> Self {
+        unimplemented!("Entity lists must be created by a World")
+    }
+
+    #[method(name = count_)]
+    fn count(&self) -> f64 {
+        self.0.len() as f64
+    }
+
+    fn get(&self, index: i32) -> Result<WrenEntity, ForeignError> {
+        self.0
+            .get(index as usize)
+            .map(|entity| WrenEntity(*entity))
+            .ok_or_else(|| {
+                foreign_error!(OutOfBounds {
+                    index,
+                    size: self.0.len(),
+                })
+            })
+    }
+
+    fn iterate(&self, index: Option<i32>) -> ArrayIterator {
+        let next = index.map(|index| index + 1).unwrap_or(0);
+
+        if next >= 0 && (next as usize) < self.0.len() {
+            ArrayIterator::Index(next)
+        } else {
+            ArrayIterator::Done
+        }
+    }
+
+    #[method(name = iteratorValue)]
+    fn iterator_value(&self, index: i32) -> Result<WrenEntity, ForeignError> {
+        self.get(index)
+    }
+}
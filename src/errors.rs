@@ -1,5 +1,5 @@
 use rust_wren::{WrenCompileError, WrenError, WrenStackFrame};
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, io};
 
 #[derive(Debug)]
 pub enum GersError {
@@ -7,7 +7,16 @@ pub enum GersError {
     Wren(WrenError),
 
     /// Error when the program was executed with incorrect command line arguments.
-    InvalidCmdArgs,
+    InvalidCmdArgs(String),
+
+    /// Error reading or writing files, or standard streams.
+    Io(io::Error),
+
+    /// Error when the project manifest is malformed.
+    InvalidManifest(String),
+
+    /// Error when packing a project into an archive.
+    Pack(String),
 }
 
 impl Error for GersError {}
@@ -16,7 +25,10 @@ impl fmt::Display for GersError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             GersError::Wren(err) => fmt::Display::fmt(err, f),
-            GersError::InvalidCmdArgs => write!(f, "Invalid command line arguments"),
+            GersError::InvalidCmdArgs(message) => write!(f, "Invalid command line arguments: {}", message),
+            GersError::Io(err) => fmt::Display::fmt(err, f),
+            GersError::InvalidManifest(message) => write!(f, "Invalid project manifest: {}", message),
+            GersError::Pack(message) => write!(f, "Packing failed: {}", message),
         }
     }
 }
@@ -29,14 +41,28 @@ impl From<WrenError> for GersError {
     }
 }
 
+impl From<io::Error> for GersError {
+    fn from(io_error: io::Error) -> Self {
+        GersError::Io(io_error)
+    }
+}
+
 /// Utility for pretty printing a Wren error, with its stack trace, to logging output.
 pub fn log_wren_error(logger: &slog::Logger, err: &WrenError) {
     match err {
         WrenError::CompileError(errors) => {
-            for WrenCompileError { module, message, line } in errors {
-                error!(logger, "Compile [{} line {}] {}", module, line, message);
+            for compile_error in errors {
+                error!(logger, "{}", format_compile_error(compile_error));
             }
         }
+        _ => error!(logger, "{}", format_wren_error(err)),
+    }
+}
+
+/// Formats a Wren error, with its stack trace, for display to the user.
+pub fn format_wren_error(err: &WrenError) -> String {
+    match err {
+        WrenError::CompileError(errors) => errors.iter().map(format_compile_error).collect::<Vec<_>>().join("\n"),
         WrenError::RuntimeError {
             message,
             foreign,
@@ -68,8 +94,13 @@ pub fn log_wren_error(logger: &slog::Logger, err: &WrenError) {
                 }
             }
 
-            error!(logger, "{}", msg);
+            msg
         }
-        _ => error!(logger, "{}", err),
+        _ => err.to_string(),
     }
 }
+
+fn format_compile_error(compile_error: &WrenCompileError) -> String {
+    let WrenCompileError { module, message, line } = compile_error;
+    format!("Compile [{} line {}] {}", module, line, message)
+}
@@ -0,0 +1,98 @@
+//! Screen shown in the window after a fatal script error.
+use crate::graphics::{GfxResult, GraphicDevice, Shader, SpriteBatch, TextImage, Texture, Transform2D};
+use glutin::{dpi::PhysicalSize, PossiblyCurrent, WindowedContext};
+
+const BACKGROUND: [u8; 4] = [32, 16, 24, 255];
+const TITLE_COLOR: [u8; 4] = [255, 96, 96, 255];
+const TEXT_COLOR: [u8; 4] = [232, 232, 232, 255];
+const HINT_COLOR: [u8; 4] = [160, 160, 160, 255];
+
+/// Renders an error message and stack trace, in place of
+/// the game, without calling into scripts.
+///
+/// Draws with its own graphic device sharing the window's
+/// OpenGL context, because the device owned by the Wren VM
+/// can't be trusted after the scripts failed.
+pub struct ErrorView {
+    message: String,
+    batch: SpriteBatch,
+    shader: Shader,
+    /// Rasterised text. Is recreated when the window is resized.
+    texture: Option<Texture>,
+    /// Dropped last so it can release the other resources.
+    device: GraphicDevice,
+}
+
+impl ErrorView {
+    /// Text size multiplier, relative to the built in font.
+    const TEXT_SCALE: u32 = 2;
+    const MARGIN: u32 = 16;
+    const HINT: &'static str = "Press R to retry init(), or Escape to quit";
+
+    pub fn new(windowed_context: &WindowedContext<PossiblyCurrent>, message: String) -> GfxResult<Self> {
+        let device = unsafe { GraphicDevice::from_windowed_context(windowed_context) };
+
+        Ok(Self {
+            message,
+            batch: SpriteBatch::create(&device)?,
+            shader: Shader::sprite(&device)?,
+            texture: None,
+            device,
+        })
+    }
+
+    /// Must be called when the window is resized.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.device.set_viewport_size(size);
+        self.texture = None;
+    }
+
+    pub fn draw(&mut self) -> GfxResult<()> {
+        let [r, g, b, a] = BACKGROUND;
+        self.device
+            .clear_screen([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0]);
+
+        if self.texture.is_none() {
+            self.texture = self.rasterise()?;
+        }
+
+        if let Some(texture) = &self.texture {
+            let [width, height] = texture.handle().size();
+            self.batch
+                .add(0.0, 0.0, width as f32, height as f32, texture, Transform2D::default());
+            self.batch.draw(&self.device, &self.shader, &Transform2D::default())?;
+        }
+
+        self.device.maintain();
+
+        Ok(())
+    }
+
+    /// Draws the text into a texture covering the viewport.
+    ///
+    /// Returns `None` when the window is minimised.
+    fn rasterise(&self) -> GfxResult<Option<Texture>> {
+        let viewport = self.device.viewport_size();
+        if viewport.width == 0 || viewport.height == 0 {
+            return Ok(None);
+        }
+
+        // Power of two size so the texture is supported everywhere.
+        // The part outside the viewport is simply not visible.
+        let width = viewport.width.next_power_of_two();
+        let height = viewport.height.next_power_of_two();
+
+        let mut image = TextImage::new(width, height, Self::TEXT_SCALE, BACKGROUND);
+        image.set_wrap_width(viewport.width.saturating_sub(Self::MARGIN));
+
+        let left = Self::MARGIN;
+        let mut y = image.draw_text([left, Self::MARGIN], "Fatal Error", TITLE_COLOR);
+        y = image.draw_text([left, y + Self::MARGIN], &self.message, TEXT_COLOR);
+        image.draw_text([left, y + Self::MARGIN], Self::HINT, HINT_COLOR);
+
+        let mut texture = Texture::create(&self.device, width, height)?;
+        texture.update_data(&self.device, image.pixels())?;
+
+        Ok(Some(texture))
+    }
+}
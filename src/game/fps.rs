@@ -1,33 +1,65 @@
+use serde::{Deserialize, Serialize};
 use std::{
+    error::Error,
+    fmt,
+    str::FromStr,
     thread,
     time::{Duration, Instant},
 };
 
+/// Limits the frame rate by waiting out the remainder of each frame.
 pub struct FpsThrottle {
-    target: Duration,
-    last_time: Instant,
+    /// Target frame duration. Is `None` when the frame rate is uncapped.
+    target: Option<Duration>,
     policy: FpsThrottlePolicy,
 }
 
 impl FpsThrottle {
-    pub fn new(target_fps: u64, policy: FpsThrottlePolicy) -> Self {
+    /// Time before the end of the frame where the hybrid
+    /// policy stops sleeping and starts spinning. Sleep is
+    /// only accurate to around a millisecond on most platforms.
+    const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+    /// Creates a throttle for the given frame rate. A target
+    /// of zero leaves the frame rate uncapped.
+    pub fn new(target_fps: u32, policy: FpsThrottlePolicy) -> Self {
         Self {
-            target: Duration::from_secs_f64(1.0 / target_fps as f64),
-            last_time: Instant::now(),
+            target: Self::frame_duration(target_fps),
             policy,
         }
     }
 
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        self.target = Self::frame_duration(target_fps);
+    }
+
+    pub fn set_policy(&mut self, policy: FpsThrottlePolicy) {
+        self.policy = policy;
+    }
+
+    fn frame_duration(target_fps: u32) -> Option<Duration> {
+        if target_fps > 0 {
+            Some(Duration::from_secs_f64(1.0 / target_fps as f64))
+        } else {
+            None
+        }
+    }
+
     /// Block the current thread until the target delta time has passed.
     ///
     /// Provide the instant measurement given during the last frame's call.
     pub fn throttle(&mut self, last_time: Instant) {
         use FpsThrottlePolicy as P;
 
-        self.last_time = last_time;
-        let mut elapsed = Instant::now() - self.last_time;
+        let target = match self.target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let target_end = last_time + target;
+        let mut now = Instant::now();
 
-        while elapsed < self.target {
+        while now < target_end {
             match self.policy {
                 P::Off => {
                     return;
@@ -36,68 +68,109 @@ impl FpsThrottle {
                     thread::yield_now();
                 }
                 P::Sleep => {
-                    let target_end = last_time + self.target;
-                    let now = Instant::now();
-                    if now < target_end {
-                        thread::sleep(Duration::from_millis(1));
+                    thread::sleep(Duration::from_millis(1));
+                }
+                P::Hybrid => {
+                    let remaining = target_end - now;
+                    if remaining > Self::SPIN_MARGIN {
+                        thread::sleep(remaining - Self::SPIN_MARGIN);
+                    } else {
+                        thread::yield_now();
                     }
                 }
             }
 
-            elapsed = Instant::now() - self.last_time;
+            now = Instant::now();
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-#[allow(dead_code)]
+/// Strategy for waiting out the remainder of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FpsThrottlePolicy {
+    /// Don't wait. The frame rate is uncapped.
     Off,
+    /// Busy wait, yielding to other threads. Precise, but
+    /// keeps a CPU core busy.
     Yield,
+    /// Sleep in small increments. Cheap, but frames may
+    /// overshoot the target by the sleep granularity.
     Sleep,
+    /// Sleep for most of the remaining time, then busy wait
+    /// for the last moment. Precise without burning the CPU.
+    Hybrid,
 }
 
-/// Utility for measuring frame rate per second.
-///
-/// It takes periodic snapshots of the measured
-/// fps to slow down the value being printed.
-/// This makes it easier to read when presented to
-/// the user.
-pub struct FpsCounter {
-    dt: [f32; Self::DATA_POINT_COUNT],
-    snapshot: f32,
-    cursor: usize,
+impl FpsThrottlePolicy {
+    /// Name used to select the policy from scripts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FpsThrottlePolicy::Off => "off",
+            FpsThrottlePolicy::Yield => "yield",
+            FpsThrottlePolicy::Sleep => "sleep",
+            FpsThrottlePolicy::Hybrid => "hybrid",
+        }
+    }
 }
 
-impl FpsCounter {
-    const DATA_POINT_COUNT: usize = 100;
+impl FromStr for FpsThrottlePolicy {
+    type Err = ParsePolicyError;
 
-    pub fn new() -> Self {
-        Self {
-            dt: [0.0; Self::DATA_POINT_COUNT],
-            snapshot: 0.0,
-            cursor: 0,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(FpsThrottlePolicy::Off),
+            "yield" => Ok(FpsThrottlePolicy::Yield),
+            "sleep" => Ok(FpsThrottlePolicy::Sleep),
+            "hybrid" => Ok(FpsThrottlePolicy::Hybrid),
+            _ => Err(ParsePolicyError(s.to_string())),
         }
     }
+}
 
-    pub fn add(&mut self, delta_time: Duration) {
-        self.dt[self.cursor] = delta_time.as_secs_f32();
-        if self.cursor == 0 {
-            self.take_snapshot();
-        }
-        self.cursor = (self.cursor + 1) % self.dt.len();
+/// Error when a throttle policy name is not recognised.
+#[derive(Debug)]
+pub struct ParsePolicyError(String);
+
+impl fmt::Display for ParsePolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown throttle policy '{}', expected 'off', 'yield', 'sleep' or 'hybrid'",
+            self.0
+        )
     }
+}
 
-    fn take_snapshot(&mut self) {
-        let sum: f32 = self.dt.iter().fold(0.0, |acc, el| acc + *el);
-        let avg = sum / self.dt.len() as f32;
-        // Approximately not zero
-        if avg.abs() > f32::EPSILON {
-            self.snapshot = 1.0 / avg;
-        }
+impl Error for ParsePolicyError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(
+            "hybrid".parse::<FpsThrottlePolicy>().unwrap(),
+            FpsThrottlePolicy::Hybrid
+        );
+        assert_eq!("off".parse::<FpsThrottlePolicy>().unwrap(), FpsThrottlePolicy::Off);
+        assert!("spin".parse::<FpsThrottlePolicy>().is_err());
+    }
+
+    #[test]
+    fn test_throttle_hybrid() {
+        let mut throttle = FpsThrottle::new(100, FpsThrottlePolicy::Hybrid);
+        let start = Instant::now();
+        throttle.throttle(start);
+        assert!(start.elapsed() >= Duration::from_millis(10));
     }
 
-    pub fn fps(&self) -> f32 {
-        self.snapshot
+    #[test]
+    fn test_throttle_uncapped() {
+        let mut throttle = FpsThrottle::new(0, FpsThrottlePolicy::Sleep);
+        let start = Instant::now();
+        throttle.throttle(start);
+        assert!(start.elapsed() < Duration::from_millis(10));
     }
 }
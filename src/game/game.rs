@@ -1,12 +1,19 @@
 //! Game script entrypoint and hooks.
 use crate::{
-    errors::{log_wren_error, GersError, GersResult},
-    game::{FpsCounter, FpsThrottle, FpsThrottlePolicy},
+    crash,
+    errors::{format_wren_error, log_wren_error, GersError, GersResult},
+    game::{
+        error_view::ErrorView, replay::new_seed, FixedTimestep, FpsThrottle, FpsThrottlePolicy, FrameStats,
+        ReplayPlayer, ReplayRecorder, WrenFrameStats,
+    },
     graphics::GraphicDeviceHooks,
-    input::{Keyboard, Mouse},
-    window::WrenWindowConfig,
+    input::{InputEvent, Keyboard, Mouse},
+    io::{BuiltinModules, FileWatcher},
+    profiler,
+    window::{init_window_control, WindowCommand, WindowControl, WindowSignal, WindowSignals, WrenWindowConfig},
 };
 use glutin::{
+    dpi::PhysicalSize,
     event::Event,
     event_loop::{ControlFlow, EventLoop},
     platform::run_return::EventLoopExtRunReturn,
@@ -15,23 +22,61 @@ use glutin::{
 use rust_wren::{
     handle::{FnSymbolRef, WrenCallHandle, WrenCallRef},
     prelude::*,
-    WrenContext, WrenResult,
+    ModuleBuilder, WrenContext, WrenError, WrenResult,
 };
 use slog::Logger;
-use std::time::{Duration, Instant};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+pub const GAME_MODULE: &str = "gers.game";
+
+/// Module of the `Bootstrap` class, which the engine calls
+/// into before the game is loaded and after it shuts down.
+pub const MAIN_MODULE: &str = "gers.main";
 
 pub fn init_game(
     ctx: &mut WrenContext,
     logger: Logger,
-    windowed_context: WindowedContext<PossiblyCurrent>,
+    windowed_context: Option<WindowedContext<PossiblyCurrent>>,
     graphic_device_hooks: GraphicDeviceHooks,
 ) -> WrenResult<Game> {
     // The user's game instance, which is the entry point from Rust into
     // the Wren program, is stored in a property.
-    let get_handler = ctx.make_call_ref("game", "Game", "handler_")?;
+    let get_handler = ctx.make_call_ref(GAME_MODULE, "Game", "handler_")?;
 
     // Delta Time
-    let set_delta_time = ctx.make_call_ref("game", "Game", "deltaTime_=(_)")?.leak()?;
+    let set_delta_time = ctx.make_call_ref(GAME_MODULE, "Game", "deltaTime_=(_)")?.leak()?;
+
+    // Interpolation alpha for drawing between fixed updates.
+    let set_alpha = ctx.make_call_ref(GAME_MODULE, "Game", "alpha_=(_)")?.leak()?;
+
+    // Quit request from script.
+    let is_quitting = ctx.make_call_ref(GAME_MODULE, "Game", "isQuitting_")?.leak()?;
+
+    // Frame statistics are shared with the script.
+    let stats = Rc::new(RefCell::new(FrameStats::new()));
+    let set_stats = ctx.make_call_ref(GAME_MODULE, "Game", "stats_=(_)")?;
+    set_stats.call::<_, ()>(ctx, WrenFrameStats(stats.clone()))?;
+
+    // Window changes requested by the script, applied by the game loop.
+    let window_control = init_window_control(ctx)?;
+    let window_signals = WindowSignals::new(ctx)?;
+
+    // Frame pacing
+    let set_pacing = ctx.make_call_ref(GAME_MODULE, "Game", "setPacing_(_,_,_)")?.leak()?;
+    let take_pacing_change = ctx.make_call_ref(GAME_MODULE, "Game", "takePacingChange_()")?.leak()?;
+    let target_fps = ctx.make_call_ref(GAME_MODULE, "Game", "targetFps")?.leak()?;
+    let throttle = ctx.make_call_ref(GAME_MODULE, "Game", "throttle")?.leak()?;
+
+    // Error handling
+    let has_error_handler = ctx.make_call_ref(GAME_MODULE, "Game", "hasErrorHandler")?.leak()?;
+    let new_error = ctx.make_call_ref(GAME_MODULE, "GameError", "new_(_,_)")?.leak()?;
+
+    // Replays
+    let set_seed = ctx.make_call_ref(GAME_MODULE, "Game", "seed_=(_)")?.leak()?;
 
     // Init
     let init = {
@@ -47,10 +92,37 @@ pub fn init_game(
         WrenCallRef::new(handler, update_ref).leak()?
     };
 
+    // Hot reload
+    let save = {
+        let handler = get_handler.call::<_, WrenRef>(ctx, ())?;
+        let save_ref = FnSymbolRef::compile(ctx, "save()")?;
+        WrenCallRef::new(handler, save_ref).leak()?
+    };
+
+    let reload = {
+        let handler = get_handler.call::<_, WrenRef>(ctx, ())?;
+        let reload_ref = FnSymbolRef::compile(ctx, "reload(_)")?;
+        WrenCallRef::new(handler, reload_ref).leak()?
+    };
+
+    // Window close
+    let close_requested = {
+        let handler = get_handler.call::<_, WrenRef>(ctx, ())?;
+        let close_ref = FnSymbolRef::compile(ctx, "closeRequested_()")?;
+        WrenCallRef::new(handler, close_ref).leak()?
+    };
+
+    // Replay checksum
+    let checksum = {
+        let handler = get_handler.call::<_, WrenRef>(ctx, ())?;
+        let checksum_ref = FnSymbolRef::compile(ctx, "checksum_()")?;
+        WrenCallRef::new(handler, checksum_ref).leak()?
+    };
+
     // Draw
     let draw_handle = {
         let handler = get_handler.call::<_, WrenRef>(ctx, ())?;
-        let draw_ref = FnSymbolRef::compile(ctx, "draw()")?;
+        let draw_ref = FnSymbolRef::compile(ctx, "draw_()")?;
         WrenCallRef::new(handler, draw_ref).leak()?
     };
 
@@ -75,60 +147,138 @@ pub fn init_game(
         windowed_context,
         graphic_hooks: graphic_device_hooks,
         scale_factor: 1.0,
+        timestep: None,
+        fps_throttle: FpsThrottle::new(0, FpsThrottlePolicy::Off),
+        stats,
+        window_control,
+        watcher: None,
+        reload_state: None,
+        seed: new_seed(),
+        recorder: None,
+        replay: None,
+        replay_desynced: false,
+        error_view: None,
+        started: false,
+        reload_requested: false,
         set_delta_time,
+        set_alpha,
+        is_quitting,
+        set_pacing,
+        take_pacing_change,
+        target_fps,
+        throttle,
+        has_error_handler,
+        new_error,
+        set_seed,
         init,
         update,
         draw_handle,
+        save,
+        reload,
+        close_requested,
+        checksum,
         mouse,
         keyboard,
+        window_signals,
     })
 }
 
 /// Register builtin game module.
-pub fn register_game(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret("game", include_str!("game.wren"))
+pub fn register_game(builtins: &mut BuiltinModules) {
+    builtins.add(GAME_MODULE, include_str!("game.wren"));
+}
+
+pub fn bind_game(module: &mut ModuleBuilder) {
+    module.register::<WrenFrameStats>();
 }
 
 pub struct Game {
     pub logger: Logger,
     pub window_conf: WrenWindowConfig,
-    pub windowed_context: WindowedContext<PossiblyCurrent>,
+    /// Window and OpenGL context. Is `None` when the game is running headless.
+    pub windowed_context: Option<WindowedContext<PossiblyCurrent>>,
     pub graphic_hooks: GraphicDeviceHooks,
     pub scale_factor: f64,
+    /// Accumulator for fixed updates. Is `None` when updates are run
+    /// once per frame with a variable delta time.
+    pub timestep: Option<FixedTimestep>,
+    /// Frame rate limiter, configured by the script.
+    pub fps_throttle: FpsThrottle,
+    /// Frame timings, also readable by the script as `Game.stats`.
+    pub stats: Rc<RefCell<FrameStats>>,
+    /// Window state and changes, shared with the script as `Window`.
+    pub window_control: Rc<RefCell<WindowControl>>,
+    /// Script files to watch for hot reloading. Is `None` when hot
+    /// reloading is disabled.
+    pub watcher: Option<FileWatcher>,
+    /// State saved by the previous game instance, to be passed
+    /// to the `reload(_)` hook after `init()`.
+    pub reload_state: Option<ReloadState>,
+    /// Pushed to the script as `Game.seed` before `init()`.
+    pub seed: u32,
+    /// Writes the input and frame timing forwarded to the script
+    /// to a file. Is `None` when not recording.
+    pub recorder: Option<ReplayRecorder>,
+    /// Recorded input and frame timing, fed to the script in place
+    /// of live input. Is `None` when not replaying.
+    pub replay: Option<ReplayPlayer>,
+    /// A checksum mismatch was reported for the replay.
+    replay_desynced: bool,
+    /// Shown in place of the game after a fatal error. While
+    /// it's `Some` the game's scripts are not called.
+    error_view: Option<ErrorView>,
+    /// Initialisation hooks have been called.
+    started: bool,
+    /// Game loop exited because scripts changed on disk.
+    reload_requested: bool,
     pub set_delta_time: WrenCallHandle,
+    pub set_alpha: WrenCallHandle,
+    pub is_quitting: WrenCallHandle,
+    pub set_pacing: WrenCallHandle,
+    pub take_pacing_change: WrenCallHandle,
+    pub target_fps: WrenCallHandle,
+    pub throttle: WrenCallHandle,
+    pub has_error_handler: WrenCallHandle,
+    pub new_error: WrenCallHandle,
+    pub set_seed: WrenCallHandle,
     pub init: WrenCallHandle,
     pub update: WrenCallHandle,
     pub draw_handle: WrenCallHandle,
+    pub save: WrenCallHandle,
+    pub reload: WrenCallHandle,
+    pub close_requested: WrenCallHandle,
+    pub checksum: WrenCallHandle,
     pub mouse: Mouse,
     pub keyboard: Keyboard,
+    pub window_signals: WindowSignals,
 }
 
 impl Game {
+    /// Number of frames between updates of the FPS in the window title.
+    const TITLE_INTERVAL: u64 = 30;
+
     /// Run the game loop.
     ///
-    /// Consumes the game and never returns.
+    /// Returns when the game quits, or when the watched scripts
+    /// changed on disk and should be reloaded. The loop can be
+    /// resumed by calling `run` again.
     ///
     /// VM is borrowed so that it's dropped after the `Game`, which
     /// contains handles that need to be released first.
-    pub fn run(mut self, vm: &mut WrenVm, mut event_loop: EventLoop<()>) -> GersResult<()> {
+    pub fn run(&mut self, vm: &mut WrenVm, event_loop: &mut EventLoop<()>) -> GersResult<RunExit> {
+        crash::set_window_conf(&self.window_conf);
+
         // Initialisation hook.
         //
         // After Window has been initialised, before event loop starts.
-        let init_result = vm.context_result(|ctx| self.init.call::<_, ()>(ctx, ()));
-
-        // TODO: Feed fatal error into an error view rendered by the game. Requires text rendering.
-        if let Err(err) = init_result {
-            error!(self.logger, "Error initialising game");
-            log_wren_error(&self.logger, &err);
-            return Err(GersError::Wren(err));
-        };
-
-        // Frame rate throttle to prevent excessive CPU usage, battery drainage
-        // and laptop fans freaking out.
-        let mut fps_throttle = FpsThrottle::new(144, FpsThrottlePolicy::Off);
-
-        // Frame rate metric.
-        let mut fps_counter = FpsCounter::new();
+        if !self.started {
+            if let Err(err) = self.start(vm) {
+                if let Err(err) = self.show_error_view(err) {
+                    return Err(self.report_crash(err));
+                }
+            }
+        }
+        self.reload_requested = false;
 
         // Time at which delta time is calculated.
         // Also serves as the mark where one frame ends and the next frame starts.
@@ -141,6 +291,9 @@ impl Game {
         // Prevent the result from being moved into the closure.
         let result_ref = &mut result;
 
+        // Prevent the game from being moved into the closure.
+        let game = &mut *self;
+
         // Event loop is a diverging function, it never returns.
         //
         // Some considerations must be taken for the drop order. The Main
@@ -158,15 +311,12 @@ impl Game {
 
                 last_time: &mut last_time,
                 delta_time: &mut delta_time,
-
-                fps_counter: &mut fps_counter,
-                fps_throttle: &mut fps_throttle,
             };
 
-            // TODO: handle error return.
-            //       Either panic or channel error to script.
-            if let Err(err) = self.handle_event(args) {
-                error!(self.logger, "Event loop error");
+            // Fatal errors are shown in the window, and only
+            // abort the event loop when that's not possible.
+            if let Err(err) = game.handle_event(args).or_else(|err| game.show_error_view(err)) {
+                error!(game.logger, "Event loop error");
 
                 // Abort event loop.
                 *control_flow = ControlFlow::Exit;
@@ -176,7 +326,584 @@ impl Game {
             }
         });
 
-        result.unwrap_or(Ok(()))
+        if let Some(Err(err)) = result {
+            return Err(self.report_crash(err));
+        }
+
+        if self.reload_requested {
+            Ok(RunExit::Reload)
+        } else {
+            Ok(RunExit::Quit)
+        }
+    }
+
+    /// Run the game loop without a window.
+    ///
+    /// Frames are advanced as fast as possible with a simulated
+    /// clock, until the frame count is reached, the replay ends,
+    /// or the script calls `Game.quit()`.
+    pub fn run_headless(mut self, vm: &mut WrenVm, headless: HeadlessConfig) -> GersResult<()> {
+        self.start(vm)?;
+
+        let mut frame_count = 0;
+
+        while headless.frames.map(|frames| frame_count < frames).unwrap_or(true) {
+            profiler::end_frame();
+            self.stats.borrow_mut().add_frame(headless.delta_time);
+            if !self.advance_frame(vm, headless.delta_time)? {
+                break;
+            }
+            self.apply_window_commands();
+            self.draw_hook(vm)?;
+            frame_count += 1;
+
+            if self.is_quitting(vm)? {
+                break;
+            }
+        }
+
+        debug!(self.logger, "Headless run ended after {} frames", frame_count);
+        self.shutdown_hook(vm);
+
+        Ok(())
+    }
+
+    /// Calls the hooks that run once, before the first frame.
+    fn start(&mut self, vm: &mut WrenVm) -> GersResult<()> {
+        // Frame rate throttle to prevent excessive CPU usage, battery drainage
+        // and laptop fans freaking out. Pushed to the script before `init()`
+        // so it can be changed there.
+        self.fps_throttle = self.window_conf.fps_throttle();
+        self.window_control.borrow_mut().configure(&self.window_conf);
+        self.refresh_window_control();
+        let conf = &self.window_conf;
+        let pacing = (conf.target_fps, conf.throttle.name().to_string(), conf.vsync);
+        vm.context_result(|ctx| {
+            self.set_pacing.call::<_, ()>(ctx, pacing)?;
+            self.set_seed.call::<_, ()>(ctx, self.seed as f64)
+        })?;
+
+        self.init_hook(vm)?;
+
+        if let Some(state) = self.reload_state.take() {
+            self.reload_hook(vm, state)?;
+        }
+
+        self.timestep = self.window_conf.timestep();
+        self.started = true;
+
+        self.apply_window_commands();
+
+        Ok(())
+    }
+
+    /// Calls the script's `save()` to retrieve the state that will be
+    /// carried over to the next game instance on hot reload.
+    pub fn save_state(&mut self, vm: &mut WrenVm) -> GersResult<ReloadState> {
+        let save_result = vm.context_result(|ctx| self.save.call::<_, Option<String>>(ctx, ()));
+
+        match save_result {
+            Ok(state) => Ok(ReloadState(state)),
+            Err(err) => {
+                error!(self.logger, "Error saving game state for reload");
+                log_wren_error(&self.logger, &err);
+                Err(GersError::Wren(err))
+            }
+        }
+    }
+
+    /// Calls the script's `reload(_)` with the state saved by the previous game instance.
+    fn reload_hook(&mut self, vm: &mut WrenVm, state: ReloadState) -> GersResult<()> {
+        let reload_result = vm.context_result(|ctx| self.reload.call::<_, ()>(ctx, state.0));
+
+        if let Err(err) = reload_result {
+            error!(self.logger, "Error reloading game state");
+            log_wren_error(&self.logger, &err);
+            return Err(GersError::Wren(err));
+        };
+
+        Ok(())
+    }
+
+    /// Calls the script's `init()`.
+    fn init_hook(&mut self, vm: &mut WrenVm) -> GersResult<()> {
+        let init_result = vm.context_result(|ctx| self.init.call::<_, ()>(ctx, ()));
+
+        if let Err(err) = init_result {
+            error!(self.logger, "Error initialising game");
+            log_wren_error(&self.logger, &err);
+            return Err(GersError::Wren(err));
+        };
+
+        Ok(())
+    }
+
+    /// Runs the frame's update. When replaying, the recorded input
+    /// and delta time are used instead, and when recording, the
+    /// frame is written to the recording.
+    ///
+    /// Returns `false` when the replay has ended.
+    fn advance_frame(&mut self, vm: &mut WrenVm, delta_time: Duration) -> GersResult<bool> {
+        if self.replay.is_some() {
+            return self.replay_frame(vm);
+        }
+
+        self.frame_update(vm, delta_time)?;
+
+        if self.recorder.is_some() {
+            let checksum = self.checksum_hook(vm)?;
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.frame(delta_time, checksum.as_deref())?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Feeds the next recorded frame to the script, and compares
+    /// the script's checksum to the recorded one.
+    fn replay_frame(&mut self, vm: &mut WrenVm) -> GersResult<bool> {
+        let replay = self.replay.as_mut().expect("Game is not replaying");
+        let frame = match replay.next_frame()? {
+            Some(frame) => frame,
+            None => {
+                info!(self.logger, "Replay finished after {} frames", replay.frame_count());
+                return Ok(false);
+            }
+        };
+        let frame_number = replay.frame_count();
+
+        vm.context_result(|ctx| {
+            for event in &frame.inputs {
+                event.send(ctx, &self.mouse, &self.keyboard)?;
+            }
+            Ok(())
+        })?;
+
+        self.frame_update(vm, frame.delta_time)?;
+
+        if let Some(expected) = frame.checksum {
+            let checksum = self.checksum_hook(vm)?;
+
+            // Later frames will most likely differ too, so only the first is reported.
+            if checksum.as_deref() != Some(expected.as_str()) && !self.replay_desynced {
+                warn!(
+                    self.logger,
+                    "Replay desynced at frame {}: recorded checksum {}, got {}",
+                    frame_number,
+                    expected,
+                    checksum.as_deref().unwrap_or("null")
+                );
+                self.replay_desynced = true;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Forwards live input to the script, and records it.
+    ///
+    /// Ignored while replaying, since input comes from the recording.
+    fn input_event(&mut self, vm: &mut WrenVm, event: InputEvent) -> GersResult<()> {
+        if self.replay.is_some() {
+            return Ok(());
+        }
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.input(&event)?;
+        }
+
+        vm.context_result(|ctx| event.send(ctx, &self.mouse, &self.keyboard))?;
+
+        Ok(())
+    }
+
+    /// Calls the script's `checksum()`, which summarises the game
+    /// state to detect replays that diverged from the recording.
+    fn checksum_hook(&mut self, vm: &mut WrenVm) -> GersResult<Option<String>> {
+        let checksum_result = vm.context_result(|ctx| self.checksum.call::<_, Option<String>>(ctx, ()));
+
+        checksum_result.map_err(|err| {
+            error!(self.logger, "Error calculating game checksum");
+            log_wren_error(&self.logger, &err);
+            GersError::Wren(err)
+        })
+    }
+
+    /// Updates the game once with the frame's delta time, or when fixed
+    /// updates are enabled, zero or more times with the fixed step.
+    fn frame_update(&mut self, vm: &mut WrenVm, delta_time: Duration) -> GersResult<()> {
+        let (steps, step) = match self.timestep.as_mut() {
+            Some(timestep) => (timestep.advance(delta_time), timestep.step()),
+            None => (1, delta_time),
+        };
+
+        let start = Instant::now();
+
+        for _ in 0..steps {
+            self.update_hook(vm, step)?;
+        }
+
+        self.stats.borrow_mut().set_update_time(start.elapsed());
+
+        Ok(())
+    }
+
+    /// Pushes the delta time, and calls the script's per frame update.
+    fn update_hook(&mut self, vm: &mut WrenVm, delta_time: Duration) -> GersResult<()> {
+        let _zone = profiler::zone("Wren update");
+        let update_result = vm.context_result(|ctx| {
+            self.set_delta_time.call::<_, ()>(ctx, delta_time.as_secs_f64())?;
+            self.update.call::<_, ()>(ctx, ())
+        });
+
+        if let Err(err) = update_result {
+            error!(self.logger, "Event loop update error");
+            log_wren_error(&self.logger, &err);
+            return self.send_error(vm, err);
+        };
+
+        Ok(())
+    }
+
+    /// Pushes the interpolation alpha, and calls the script's `draw()`.
+    fn draw_hook(&mut self, vm: &mut WrenVm) -> GersResult<()> {
+        let alpha = self.timestep.as_ref().map(FixedTimestep::alpha).unwrap_or(1.0);
+
+        let start = Instant::now();
+        let zone = profiler::zone("Wren draw");
+        let draw_result = vm.context_result(|ctx| {
+            self.set_alpha.call::<_, ()>(ctx, alpha)?;
+            self.draw_handle.call::<_, ()>(ctx, ())
+        });
+        drop(zone);
+        self.stats.borrow_mut().set_draw_time(start.elapsed());
+
+        if let Err(err) = draw_result {
+            error!(self.logger, "Event loop redraw requested error");
+            log_wren_error(&self.logger, &err);
+            return self.send_error(vm, err);
+        };
+
+        Ok(())
+    }
+
+    /// Sends a runtime error to the script's `Game.onError` handlers,
+    /// as a `GameError` with the message and stack trace.
+    ///
+    /// Returns the error back when it can't be handled, because
+    /// no handler was added, or the handler itself failed.
+    fn send_error(&mut self, vm: &mut WrenVm, err: WrenError) -> GersResult<()> {
+        let (kind, message, frames) = match &err {
+            WrenError::RuntimeError {
+                message,
+                foreign,
+                stack,
+            } => {
+                let (kind, message) = match foreign {
+                    Some(foreign) => ("foreign", foreign.to_string()),
+                    None => ("runtime", message.clone()),
+                };
+
+                let frames = stack
+                    .iter()
+                    .map(|frame| {
+                        (
+                            frame.module.clone(),
+                            frame.line as f64,
+                            frame.function.clone(),
+                            frame.is_foreign,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                (kind.to_string(), message, frames)
+            }
+            _ => return Err(GersError::Wren(err)),
+        };
+
+        let has_handler = vm.context_result(|ctx| self.has_error_handler.call::<_, bool>(ctx, ()))?;
+        if !has_handler {
+            return Err(GersError::Wren(err));
+        }
+
+        let send_result = vm.context_result(|ctx| {
+            let mut error = self.new_error.call::<_, WrenRef>(ctx, (kind, message))?;
+
+            // Each call returns the error object, to be the receiver of the next.
+            for frame in frames {
+                let add_frame = FnSymbolRef::compile(ctx, "addFrame_(_,_,_,_)")?;
+                error = WrenCallRef::new(error, add_frame).call::<_, WrenRef>(ctx, frame)?;
+            }
+
+            let send = FnSymbolRef::compile(ctx, "send_()")?;
+            WrenCallRef::new(error, send).call::<_, ()>(ctx, ())
+        });
+
+        match send_result {
+            Ok(_) => {
+                info!(self.logger, "Error handled by Game.onError");
+                Ok(())
+            }
+            Err(handler_err) => {
+                error!(self.logger, "Error handler failed");
+                log_wren_error(&self.logger, &handler_err);
+                Err(GersError::Wren(handler_err))
+            }
+        }
+    }
+
+    /// Applies changes the script made to the frame rate target or throttle policy.
+    fn update_pacing(&mut self, vm: &mut WrenVm) -> GersResult<()> {
+        let pacing = vm.context_result(|ctx| {
+            if self.take_pacing_change.call::<_, bool>(ctx, ())? {
+                let target_fps = self.target_fps.call::<_, f64>(ctx, ())?;
+                let throttle = self.throttle.call::<_, String>(ctx, ())?;
+                Ok(Some((target_fps, throttle)))
+            } else {
+                Ok(None)
+            }
+        })?;
+
+        if let Some((target_fps, throttle)) = pacing {
+            debug!(
+                self.logger,
+                "Frame pacing changed: {} FPS, {} throttle", target_fps, throttle
+            );
+            self.fps_throttle.set_target_fps(target_fps.max(0.0) as u32);
+            match throttle.parse() {
+                Ok(policy) => self.fps_throttle.set_policy(policy),
+                Err(err) => warn!(self.logger, "{}", err),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Emits one of the script's `Window` signals.
+    fn window_signal_hook(&mut self, vm: &mut WrenVm, signal: WindowSignal) -> GersResult<()> {
+        let signal_result = vm.context_result(|ctx| signal.send(ctx, &self.window_signals));
+
+        if let Err(err) = signal_result {
+            error!(self.logger, "Error handling window event {:?}", signal);
+            log_wren_error(&self.logger, &err);
+            return self.send_error(vm, err);
+        };
+
+        Ok(())
+    }
+
+    /// Checks whether the script has called `Game.quit()`.
+    fn is_quitting(&mut self, vm: &mut WrenVm) -> GersResult<bool> {
+        vm.context_result(|ctx| self.is_quitting.call::<_, bool>(ctx, ()))
+            .map_err(GersError::Wren)
+    }
+
+    /// Asks the script whether the window may be closed.
+    fn close_requested_hook(&mut self, vm: &mut WrenVm) -> GersResult<bool> {
+        let close_result = vm.context_result(|ctx| self.close_requested.call::<_, bool>(ctx, ()));
+
+        match close_result {
+            Ok(close) => Ok(close),
+            Err(err) => {
+                error!(self.logger, "Error handling close request");
+                log_wren_error(&self.logger, &err);
+                self.send_error(vm, err).map(|_| false)
+            }
+        }
+    }
+
+    /// Calls the bootstrap's `shutdown()`, which forwards to the game's `shutdown()`,
+    /// and finishes the recording.
+    ///
+    /// Errors are only logged, since the application is exiting anyway.
+    fn shutdown_hook(&mut self, vm: &mut WrenVm) {
+        let shutdown_result = vm.context_result(|ctx| {
+            let receiver = ctx
+                .get_var(MAIN_MODULE, "Bootstrap")
+                .expect("Failed to lookup Bootstrap class");
+            let func = FnSymbolRef::compile(ctx, "shutdown()")?;
+            let call_ref = WrenCallRef::new(receiver, func);
+            call_ref.call::<_, ()>(ctx, ())
+        });
+
+        if let Err(err) = shutdown_result {
+            error!(self.logger, "Error shutting down game");
+            log_wren_error(&self.logger, &err);
+        }
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.flush() {
+                error!(self.logger, "Error writing recording: {}", err);
+            }
+        }
+    }
+
+    /// Replaces the game in the window with a view of the error,
+    /// where the user can retry `init()` or quit.
+    ///
+    /// Returns the error back when it can't be shown, because the
+    /// game is headless or the view failed to initialise.
+    fn show_error_view(&mut self, err: GersError) -> GersResult<()> {
+        let windowed_context = match self.windowed_context.as_ref() {
+            Some(windowed_context) => windowed_context,
+            None => return Err(err),
+        };
+
+        let message = match &err {
+            GersError::Wren(wren_err) => format_wren_error(wren_err),
+            _ => err.to_string(),
+        };
+
+        match ErrorView::new(windowed_context, message) {
+            Ok(error_view) => {
+                let window = windowed_context.window();
+                window.set_title(&format!("{} - Error", self.window_conf.title));
+                window.request_redraw();
+
+                self.error_view = Some(error_view);
+                Ok(())
+            }
+            Err(gfx_err) => {
+                error!(self.logger, "Failed to create error view: {}", gfx_err);
+                Err(err)
+            }
+        }
+    }
+
+    /// Writes a crash report for an error that ended the game loop.
+    fn report_crash(&self, err: GersError) -> GersError {
+        match crash::report_error(&err) {
+            Ok(Some(path)) => error!(self.logger, "Crash report written to {}", path.display()),
+            Ok(None) => {}
+            Err(report_err) => error!(self.logger, "Failed to write crash report: {}", report_err),
+        }
+
+        err
+    }
+
+    /// Leaves the error view and calls the script's `init()` again.
+    fn retry_init(&mut self, vm: &mut WrenVm) -> GersResult<()> {
+        info!(self.logger, "Retrying game initialisation");
+        self.error_view = None;
+
+        if let Err(err) = self.start(vm) {
+            return self.show_error_view(err);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the watched scripts, and exits the event loop for a
+    /// hot reload when any have changed.
+    ///
+    /// Returns `true` when a reload was requested.
+    fn poll_watcher(&mut self, control_flow: &mut ControlFlow) -> bool {
+        if let Some(watcher) = self.watcher.as_mut() {
+            let changed = watcher.poll();
+            if !changed.is_empty() {
+                for path in changed {
+                    info!(self.logger, "Script changed: {}", path.display());
+                }
+
+                self.reload_requested = true;
+                *control_flow = ControlFlow::Exit;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Resizes the window's surface and the graphic device's viewport.
+    fn resize(&mut self, vm: &mut WrenVm, inner_size: PhysicalSize<u32>) {
+        // Required on some platforms.
+        self.windowed_context().resize(inner_size);
+
+        vm.context(|ctx| {
+            self.graphic_hooks
+                .set_viewport_handle
+                .call::<_, ()>(ctx, (inner_size.width, inner_size.height))
+                .unwrap();
+        });
+
+        if let Some(error_view) = self.error_view.as_mut() {
+            error_view.resize(inner_size);
+        }
+    }
+
+    /// Applies the window changes the script requested since the last frame.
+    ///
+    /// Without a window only the title is kept, so scripts still see
+    /// their changes. Failures are logged, since the script can't act
+    /// on them after its hooks returned.
+    fn apply_window_commands(&mut self) {
+        let commands = self.window_control.borrow_mut().take_commands();
+        if commands.is_empty() {
+            return;
+        }
+
+        for command in commands {
+            if let WindowCommand::Title(title) = &command {
+                self.window_conf.title = title.clone();
+            }
+
+            if let Some(windowed_context) = self.windowed_context.as_ref() {
+                if let Err(err) = command.apply(windowed_context.window()) {
+                    warn!(self.logger, "Window change failed: {}", err);
+                }
+            }
+        }
+
+        // The size and position requested may not be applied yet, so
+        // they're left to the resized and moved events to update.
+        if let Some(windowed_context) = self.windowed_context.as_ref() {
+            self.window_control
+                .borrow_mut()
+                .refresh_fullscreen(windowed_context.window());
+        }
+    }
+
+    /// Copies the window's state, and its monitors, to what the script sees.
+    fn refresh_window_control(&self) {
+        if let Some(windowed_context) = self.windowed_context.as_ref() {
+            self.window_control.borrow_mut().refresh(windowed_context.window());
+        }
+    }
+
+    /// Copies the window's size and position to what the script sees.
+    fn refresh_window_geometry(&self) {
+        if let Some(windowed_context) = self.windowed_context.as_ref() {
+            self.window_control
+                .borrow_mut()
+                .refresh_geometry(windowed_context.window());
+        }
+    }
+
+    /// Shows the frame rate in the window title, when enabled.
+    ///
+    /// Only updated periodically, so the number is readable.
+    fn update_title(&self) {
+        if !self.window_conf.show_fps {
+            return;
+        }
+
+        let stats = self.stats.borrow();
+        if stats.frame_count() % Self::TITLE_INTERVAL == 0 {
+            self.windowed_context()
+                .window()
+                .set_title(&format!("{} - {:.2} FPS", self.window_conf.title, stats.fps()));
+        }
+    }
+
+    /// Window and OpenGL context of a windowed game.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the game is running headless.
+    #[inline]
+    fn windowed_context(&self) -> &WindowedContext<PossiblyCurrent> {
+        self.windowed_context.as_ref().expect("Game loop requires a window")
     }
 
     /// Dispatch single event.
@@ -191,141 +918,135 @@ impl Game {
 
             last_time,
             delta_time,
-
-            fps_counter,
-            fps_throttle,
         } = args;
 
+        if self.error_view.is_some() {
+            return self.handle_error_event(vm, event, control_flow, last_time);
+        }
+
+        let _zone = profiler::zone("Game::handle_event");
+
         match event {
             E::NewEvents(_) => {
                 // Boundary where frame starts.
+                profiler::end_frame();
                 let now = Instant::now();
                 *delta_time = now - *last_time;
                 *last_time = now;
 
-                fps_counter.add(*delta_time);
+                self.stats.borrow_mut().add_frame(*delta_time);
                 Ok(())
             }
-            E::WindowEvent { ref event, window_id } if window_id == self.windowed_context.window().id() => {
+            E::WindowEvent { ref event, window_id } if window_id == self.windowed_context().window().id() => {
                 match event {
                     WE::CloseRequested => {
-                        *control_flow = ControlFlow::Exit;
+                        if self.close_requested_hook(vm)? {
+                            *control_flow = ControlFlow::Exit;
+                        }
                         Ok(())
                     }
                     WE::Resized(inner_size) => {
-                        // Required on some platforms.
-                        self.windowed_context.resize(*inner_size);
-
-                        // self.graphics.set_viewport_size(*inner_size);
-                        vm.context(|ctx| {
-                            self.graphic_hooks
-                                .set_viewport_handle
-                                .call::<_, ()>(ctx, (inner_size.width, inner_size.height))
-                                .unwrap();
-                        });
-
+                        self.resize(vm, *inner_size);
+                        self.refresh_window_geometry();
                         Ok(())
                     }
-                    WE::ScaleFactorChanged { scale_factor, .. } => {
-                        self.scale_factor = *scale_factor;
+                    WE::Moved(_) => {
+                        self.refresh_window_geometry();
                         Ok(())
                     }
-                    WE::MouseInput { button, state, .. } => {
-                        vm.context(|ctx| {
-                            self.mouse.push_button(ctx, *button, *state).unwrap();
-                        });
+                    WE::ScaleFactorChanged { scale_factor, .. } => {
+                        self.scale_factor = *scale_factor;
+                        self.refresh_window_control();
                         Ok(())
                     }
+                    WE::MouseInput { button, state, .. } => match InputEvent::mouse_button(*button, *state) {
+                        Some(input_event) => self.input_event(vm, input_event),
+                        None => Ok(()),
+                    },
                     WE::CursorMoved { position, .. } => {
-                        vm.context(|ctx| {
-                            let logical = position.to_logical(self.scale_factor);
-                            self.mouse.set_pos(ctx, logical, *position).unwrap();
-                        });
-                        Ok(())
-                    }
-                    WE::ReceivedCharacter(c) => {
-                        vm.context(|ctx| {
-                            self.keyboard.push_char(ctx, *c).unwrap();
-                        });
-                        Ok(())
+                        let logical = position.to_logical(self.scale_factor);
+                        self.input_event(vm, InputEvent::mouse_pos(logical, *position))
                     }
+                    WE::ReceivedCharacter(c) => self.input_event(vm, InputEvent::Char(*c)),
                     WE::KeyboardInput { input, .. } => {
                         if let Some(virtual_keycode) = input.virtual_keycode {
-                            vm.context(|ctx| {
-                                self.keyboard.set_key_state(ctx, virtual_keycode, input.state).unwrap();
-                            });
+                            self.input_event(vm, InputEvent::key(virtual_keycode, input.state))?;
                         };
 
-                        // TODO: Script should decide what to do with escape
+                        // Opt-in shortcut, otherwise scripts decide what Escape does.
                         if let KeyboardInput {
                             state: ElementState::Pressed,
                             virtual_keycode: Some(VirtualKeyCode::Escape),
                             ..
                         } = input
                         {
-                            *control_flow = ControlFlow::Exit;
+                            if self.window_conf.exit_on_escape {
+                                *control_flow = ControlFlow::Exit;
+                            }
                         }
 
                         Ok(())
                     }
                     _ => Ok(()),
+                }?;
+
+                // Lifecycle events also reach scripts, once the game is up to date.
+                match WindowSignal::from_event(event, self.scale_factor) {
+                    Some(signal) => self.window_signal_hook(vm, signal),
+                    None => Ok(()),
                 }
             }
             E::MainEventsCleared => {
+                // Scripts are polled before the update, so a reload
+                // doesn't happen half way through a frame.
+                if self.poll_watcher(control_flow) {
+                    return Ok(());
+                }
+
                 // Frame update after events have been flushed.
-                let update_result = vm.context_result(|ctx| {
-                    self.set_delta_time.call::<_, ()>(ctx, delta_time.as_secs_f64())?;
-                    self.update.call::<_, ()>(ctx, ())
-                });
-
-                // TODO: Feed fatal error into an error view rendered by the game. Requires text rendering.
-                if let Err(err) = update_result {
-                    error!(self.logger, "Event loop update error");
-                    log_wren_error(&self.logger, &err);
-                    return Err(GersError::Wren(err));
-                };
+                if !self.advance_frame(vm, *delta_time)? {
+                    *control_flow = ControlFlow::Exit;
+                    return Ok(());
+                }
+
+                if self.is_quitting(vm)? {
+                    *control_flow = ControlFlow::Exit;
+                    return Ok(());
+                }
+
+                self.update_pacing(vm)?;
 
-                self.windowed_context.window().set_title(&format!(
-                    "{} - {:.2} FPS",
-                    self.window_conf.title,
-                    fps_counter.fps()
-                ));
+                self.apply_window_commands();
+
+                self.update_title();
 
                 // Emit redraw event for rendering. Integrates
                 // our render step with redraw requests from OS.
-                self.windowed_context.window().request_redraw();
+                self.windowed_context().window().request_redraw();
 
                 Ok(())
             }
             E::RedrawRequested(_window_id) => {
-                let draw_result = vm.context_result(|ctx| self.draw_handle.call::<_, ()>(ctx, ()));
-
-                if let Err(err) = draw_result {
-                    error!(self.logger, "Event loop redraw requested error");
-                    log_wren_error(&self.logger, &err);
-                    return Err(GersError::Wren(err));
-                };
+                self.draw_hook(vm)?;
 
                 // Display the drawn buffer in the window.
-                self.windowed_context.swap_buffers().unwrap();
+                let _zone = profiler::zone("swap_buffers");
+                self.windowed_context().swap_buffers().unwrap();
 
                 Ok(())
             }
             E::RedrawEventsCleared => {
                 // Fill up the rest of the frame so we can hit the target FPS.
-                fps_throttle.throttle(*last_time);
+                self.fps_throttle.throttle(*last_time);
                 Ok(())
             }
             E::LoopDestroyed => {
                 debug!(self.logger, "Loop destroyed");
-                vm.context(|ctx| {
-                    let receiver = ctx
-                        .get_var("main", "Bootstrap")
-                        .expect("Failed to lookup Bootstrap class");
-                    let func = FnSymbolRef::compile(ctx, "shutdown()").unwrap();
-                    let call_ref = WrenCallRef::new(receiver, func);
-                    call_ref.call::<_, ()>(ctx, ()).unwrap();
-                });
+
+                // Game is not shutting down when it will be replaced by reloaded scripts.
+                if !self.reload_requested {
+                    self.shutdown_hook(vm);
+                }
 
                 // Release reference before VM is dropped.
                 // drop(self);
@@ -334,6 +1055,110 @@ impl Game {
             _ => Ok(()),
         }
     }
+
+    /// Dispatch single event while the error view is shown.
+    ///
+    /// Events are not forwarded to scripts. The loop waits for
+    /// events instead of polling, since nothing is animated.
+    fn handle_error_event(
+        &mut self,
+        vm: &mut WrenVm,
+        event: Event<()>,
+        control_flow: &mut ControlFlow,
+        last_time: &mut Instant,
+    ) -> GersResult<()> {
+        use winit::event::{ElementState, Event as E, KeyboardInput, VirtualKeyCode, WindowEvent as WE};
+
+        // Wake up to poll the watcher, so fixing the script hot reloads.
+        *control_flow = match self.watcher.as_ref() {
+            Some(watcher) => ControlFlow::WaitUntil(Instant::now() + watcher.interval()),
+            None => ControlFlow::Wait,
+        };
+
+        match event {
+            E::WindowEvent { ref event, window_id } if window_id == self.windowed_context().window().id() => {
+                match event {
+                    WE::CloseRequested => {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    WE::Resized(inner_size) => {
+                        self.resize(vm, *inner_size);
+                        self.windowed_context().window().request_redraw();
+                    }
+                    WE::ScaleFactorChanged { scale_factor, .. } => {
+                        self.scale_factor = *scale_factor;
+                    }
+                    WE::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(virtual_keycode),
+                                ..
+                            },
+                        ..
+                    } => match virtual_keycode {
+                        VirtualKeyCode::R => {
+                            self.retry_init(vm)?;
+
+                            // The time the error was shown is not part of the next frame.
+                            if self.error_view.is_none() {
+                                *last_time = Instant::now();
+                            }
+                        }
+                        VirtualKeyCode::Escape => *control_flow = ControlFlow::Exit,
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            E::MainEventsCleared => {
+                self.poll_watcher(control_flow);
+            }
+            E::RedrawRequested(_window_id) => {
+                if let Some(error_view) = self.error_view.as_mut() {
+                    if let Err(err) = error_view.draw() {
+                        error!(self.logger, "Error view draw error: {}", err);
+                    }
+                }
+
+                self.windowed_context().swap_buffers().unwrap();
+            }
+            E::LoopDestroyed => {
+                debug!(self.logger, "Loop destroyed");
+
+                if !self.reload_requested {
+                    self.shutdown_hook(vm);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Reason the game loop exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunExit {
+    /// Game is done and the application should exit.
+    Quit,
+    /// Scripts changed on disk and should be hot reloaded.
+    Reload,
+}
+
+/// State carried over to a new game instance when scripts are hot
+/// reloaded. Serialized by the script's `save()` hook.
+#[derive(Debug, Clone)]
+pub struct ReloadState(pub Option<String>);
+
+/// Settings for running the game loop without a window.
+#[derive(Debug, Clone)]
+pub struct HeadlessConfig {
+    /// Number of frames to run. When `None` the loop
+    /// runs until the script calls `Game.quit()`.
+    pub frames: Option<u64>,
+    /// Simulated time that passes each frame.
+    pub delta_time: Duration,
 }
 
 struct FrameArgs<'a, 'b> {
@@ -343,7 +1168,4 @@ struct FrameArgs<'a, 'b> {
 
     last_time: &'a mut Instant,
     delta_time: &'a mut Duration,
-
-    fps_throttle: &'a mut FpsThrottle,
-    fps_counter: &'a mut FpsCounter,
 }
@@ -1,5 +1,14 @@
-pub use self::fps::{FpsCounter, FpsThrottle, FpsThrottlePolicy};
-pub use self::game::{init_game, register_game, Game};
+pub use self::fps::{FpsThrottle, FpsThrottlePolicy};
+pub use self::game::{
+    bind_game, init_game, register_game, Game, HeadlessConfig, ReloadState, RunExit, GAME_MODULE, MAIN_MODULE,
+};
+pub use self::replay::{ReplayPlayer, ReplayRecorder};
+pub use self::stats::{FrameStats, WrenFrameStats};
+pub use self::timestep::FixedTimestep;
 
+mod error_view;
 mod fps;
 mod game;
+mod replay;
+mod stats;
+mod timestep;
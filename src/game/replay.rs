@@ -0,0 +1,334 @@
+//! Recording and playback of the input and frame timing fed to scripts.
+//!
+//! A replay file starts with a header holding the seed given to
+//! scripts, followed by a stream of records. Input records are
+//! written as events are forwarded, and each frame is closed by
+//! a frame record with its delta time, in nanoseconds, and the
+//! script's checksum.
+//!
+//! All numbers are little endian.
+use crate::input::InputEvent;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const MAGIC: &[u8; 4] = b"GRPL";
+const VERSION: u8 = 1;
+
+const TAG_FRAME: u8 = 0;
+const TAG_MOUSE_POS: u8 = 1;
+const TAG_MOUSE_BUTTON: u8 = 2;
+const TAG_KEY_PRESS: u8 = 3;
+const TAG_KEY_RELEASE: u8 = 4;
+const TAG_CHAR: u8 = 5;
+
+/// Seed for a new game, which isn't replaying a recording.
+pub fn new_seed() -> u32 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    now.subsec_nanos() ^ now.as_secs() as u32
+}
+
+/// Writes the events of a running game to a replay file.
+pub struct ReplayRecorder<W: Write = BufWriter<File>> {
+    writer: W,
+}
+
+impl ReplayRecorder {
+    pub fn create<P: AsRef<Path>>(path: P, seed: u32) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), seed)
+    }
+}
+
+impl<W: Write> ReplayRecorder<W> {
+    pub fn new(mut writer: W, seed: u32) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&seed.to_le_bytes())?;
+
+        Ok(Self { writer })
+    }
+
+    pub fn input(&mut self, event: &InputEvent) -> io::Result<()> {
+        let w = &mut self.writer;
+
+        match event {
+            InputEvent::MousePos { logical, physical } => {
+                w.write_all(&[TAG_MOUSE_POS])?;
+                for value in logical.iter().chain(physical.iter()) {
+                    w.write_all(&value.to_le_bytes())?;
+                }
+            }
+            InputEvent::MouseButton { button, pressed } => {
+                w.write_all(&[TAG_MOUSE_BUTTON, *button, *pressed as u8])?;
+            }
+            InputEvent::KeyPress(name) => {
+                w.write_all(&[TAG_KEY_PRESS])?;
+                write_short_str(w, name)?;
+            }
+            InputEvent::KeyRelease(name) => {
+                w.write_all(&[TAG_KEY_RELEASE])?;
+                write_short_str(w, name)?;
+            }
+            InputEvent::Char(c) => {
+                w.write_all(&[TAG_CHAR])?;
+                w.write_all(&(*c as u32).to_le_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ends the current frame.
+    pub fn frame(&mut self, delta_time: Duration, checksum: Option<&str>) -> io::Result<()> {
+        let w = &mut self.writer;
+        w.write_all(&[TAG_FRAME])?;
+        w.write_all(&(delta_time.as_nanos() as u64).to_le_bytes())?;
+
+        // Zero length means the script gave no checksum.
+        let checksum = checksum.unwrap_or("").as_bytes();
+        if checksum.len() > u16::MAX as usize {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "checksum is too long"));
+        }
+        w.write_all(&(checksum.len() as u16).to_le_bytes())?;
+        w.write_all(checksum)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Input and timing of a single recorded frame.
+#[derive(Debug, PartialEq)]
+pub struct ReplayFrame {
+    /// Input events, in the order they were forwarded to scripts.
+    pub inputs: Vec<InputEvent>,
+    pub delta_time: Duration,
+    /// Checksum the script returned at the end of the frame.
+    pub checksum: Option<String>,
+}
+
+/// Reads frames back from a replay file.
+pub struct ReplayPlayer<R: Read = BufReader<File>> {
+    reader: R,
+    seed: u32,
+    frame_count: u64,
+}
+
+impl ReplayPlayer {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> ReplayPlayer<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a replay file"));
+        }
+
+        let version = read_u8(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported replay version {}", version)));
+        }
+
+        let mut seed = [0; 4];
+        reader.read_exact(&mut seed)?;
+
+        Ok(Self {
+            reader,
+            seed: u32::from_le_bytes(seed),
+            frame_count: 0,
+        })
+    }
+
+    /// Seed that scripts were given when the replay was recorded.
+    #[inline]
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Number of frames read so far.
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Reads the next frame. Returns `None` at the end of the replay.
+    ///
+    /// Input recorded after the last frame, when the game exited
+    /// before finishing it, is discarded.
+    pub fn next_frame(&mut self) -> io::Result<Option<ReplayFrame>> {
+        let mut inputs = vec![];
+
+        loop {
+            let mut tag = [0; 1];
+            if self.reader.read(&mut tag)? == 0 {
+                return Ok(None);
+            }
+
+            let r = &mut self.reader;
+            match tag[0] {
+                TAG_FRAME => {
+                    let delta_time = read_u64(r)?;
+                    let checksum_len = read_u16(r)? as usize;
+                    let checksum = if checksum_len > 0 {
+                        Some(read_str(r, checksum_len)?)
+                    } else {
+                        None
+                    };
+
+                    self.frame_count += 1;
+
+                    return Ok(Some(ReplayFrame {
+                        inputs,
+                        delta_time: Duration::from_nanos(delta_time),
+                        checksum,
+                    }));
+                }
+                TAG_MOUSE_POS => {
+                    let logical = [read_f64(r)?, read_f64(r)?];
+                    let physical = [read_f64(r)?, read_f64(r)?];
+                    inputs.push(InputEvent::MousePos { logical, physical });
+                }
+                TAG_MOUSE_BUTTON => {
+                    let button = read_u8(r)?;
+                    let pressed = read_u8(r)? != 0;
+                    inputs.push(InputEvent::MouseButton { button, pressed });
+                }
+                TAG_KEY_PRESS => {
+                    let len = read_u8(r)? as usize;
+                    inputs.push(InputEvent::KeyPress(read_str(r, len)?));
+                }
+                TAG_KEY_RELEASE => {
+                    let len = read_u8(r)? as usize;
+                    inputs.push(InputEvent::KeyRelease(read_str(r, len)?));
+                }
+                TAG_CHAR => {
+                    let mut buf = [0; 4];
+                    r.read_exact(&mut buf)?;
+                    let c = std::char::from_u32(u32::from_le_bytes(buf))
+                        .ok_or_else(|| invalid_data("invalid character"))?;
+                    inputs.push(InputEvent::Char(c));
+                }
+                tag => return Err(invalid_data(format!("unknown record tag {}", tag))),
+            }
+        }
+    }
+}
+
+/// Key names are short, so their length fits in a byte.
+fn write_short_str<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    if s.len() > u8::MAX as usize {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "key name is too long"));
+    }
+    w.write_all(&[s.len() as u8])?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R: Read>(r: &mut R) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f64<R: Read>(r: &mut R) -> io::Result<f64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+fn read_str<R: Read>(r: &mut R, len: usize) -> io::Result<String> {
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| invalid_data("invalid UTF-8 string"))
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let inputs = vec![
+            InputEvent::MousePos {
+                logical: [10.0, 20.5],
+                physical: [20.0, 41.0],
+            },
+            InputEvent::MouseButton {
+                button: 1,
+                pressed: true,
+            },
+            InputEvent::KeyPress("Space".to_string()),
+            InputEvent::Char('é'),
+            InputEvent::KeyRelease("Space".to_string()),
+        ];
+
+        let mut recorder = ReplayRecorder::new(vec![], 1234).unwrap();
+        for event in &inputs {
+            recorder.input(event).unwrap();
+        }
+        recorder.frame(Duration::from_millis(16), Some("abc")).unwrap();
+        recorder.frame(Duration::from_millis(17), None).unwrap();
+        // Unfinished frame.
+        recorder.input(&InputEvent::Char('x')).unwrap();
+
+        let mut player = ReplayPlayer::new(recorder.writer.as_slice()).unwrap();
+        assert_eq!(player.seed(), 1234);
+        assert_eq!(
+            player.next_frame().unwrap(),
+            Some(ReplayFrame {
+                inputs,
+                delta_time: Duration::from_millis(16),
+                checksum: Some("abc".to_string()),
+            })
+        );
+        assert_eq!(
+            player.next_frame().unwrap(),
+            Some(ReplayFrame {
+                inputs: vec![],
+                delta_time: Duration::from_millis(17),
+                checksum: None,
+            })
+        );
+        assert_eq!(player.next_frame().unwrap(), None);
+        assert_eq!(player.frame_count(), 2);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(ReplayPlayer::new(&b"GRPX\x01\0\0\0\0"[..]).is_err());
+        assert!(ReplayPlayer::new(&b"GRPL\x02\0\0\0\0"[..]).is_err());
+
+        let mut player = ReplayPlayer::new(&b"GRPL\x01\0\0\0\0\x09"[..]).unwrap();
+        assert!(player.next_frame().is_err());
+
+        // Truncated record.
+        let mut player = ReplayPlayer::new(&b"GRPL\x01\0\0\0\0\x00\0\0\0"[..]).unwrap();
+        assert!(player.next_frame().is_err());
+    }
+}
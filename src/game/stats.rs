@@ -0,0 +1,237 @@
+//! Frame timing statistics.
+use rust_wren::prelude::*;
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, time::Duration};
+
+/// Frame times over a sliding window, and the time spent in the
+/// script's hooks during the last frame.
+#[derive(Debug)]
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    /// Exponential moving average of the frame time, in seconds.
+    average: f64,
+    frame_count: u64,
+    update_time: Duration,
+    draw_time: Duration,
+}
+
+impl FrameStats {
+    /// Number of frames kept for min, max and percentiles.
+    pub const WINDOW_SIZE: usize = 120;
+
+    /// Weight of the newest frame in the moving average.
+    const SMOOTHING: f64 = 0.05;
+
+    pub fn new() -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(Self::WINDOW_SIZE),
+            average: 0.0,
+            frame_count: 0,
+            update_time: Duration::default(),
+            draw_time: Duration::default(),
+        }
+    }
+
+    /// Records the duration of a frame.
+    pub fn add_frame(&mut self, delta_time: Duration) {
+        if self.frame_times.len() >= Self::WINDOW_SIZE {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta_time);
+
+        let seconds = delta_time.as_secs_f64();
+        if self.frame_count == 0 {
+            self.average = seconds;
+        } else {
+            self.average += (seconds - self.average) * Self::SMOOTHING;
+        }
+
+        self.frame_count += 1;
+    }
+
+    /// Time spent in the script's update during the last frame,
+    /// including all fixed steps.
+    pub fn set_update_time(&mut self, update_time: Duration) {
+        self.update_time = update_time;
+    }
+
+    /// Time spent in the script's draw during the last frame.
+    pub fn set_draw_time(&mut self, draw_time: Duration) {
+        self.draw_time = draw_time;
+    }
+
+    /// Frames per second, smoothed over recent frames.
+    pub fn fps(&self) -> f64 {
+        if self.average > f64::EPSILON {
+            1.0 / self.average
+        } else {
+            0.0
+        }
+    }
+
+    /// Total number of frames since the game started.
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Duration of the last frame.
+    pub fn frame_time(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    pub fn min_frame_time(&self) -> Duration {
+        self.frame_times.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max_frame_time(&self) -> Duration {
+        self.frame_times.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Frame time that the given percentage of frames in the
+    /// window are equal to or faster than.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::default();
+        }
+
+        let mut sorted = self.frame_times.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+
+        // Nearest rank method.
+        let rank = (percent.max(0.0).min(100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.max(1) - 1]
+    }
+
+    #[inline]
+    pub fn update_time(&self) -> Duration {
+        self.update_time
+    }
+
+    #[inline]
+    pub fn draw_time(&self) -> Duration {
+        self.draw_time
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Script view of the statistics, shared with the game loop which
+/// keeps them up to date. Times are given in milliseconds.
+#[wren_class(name = FrameStats)]
+pub struct WrenFrameStats(pub Rc<RefCell<FrameStats>>);
+
+#[wren_methods]
+impl WrenFrameStats {
+    #[construct]
+    fn new_() -> Self {
+        unimplemented!("Frame statistics must be created from Rust")
+    }
+
+    #[method(name = fps_)]
+    fn fps(&self) -> f64 {
+        self.0.borrow().fps()
+    }
+
+    #[method(name = frameCount_)]
+    fn frame_count(&self) -> f64 {
+        self.0.borrow().frame_count() as f64
+    }
+
+    #[method(name = frameTime_)]
+    fn frame_time(&self) -> f64 {
+        as_millis(self.0.borrow().frame_time())
+    }
+
+    #[method(name = minFrameTime_)]
+    fn min_frame_time(&self) -> f64 {
+        as_millis(self.0.borrow().min_frame_time())
+    }
+
+    #[method(name = maxFrameTime_)]
+    fn max_frame_time(&self) -> f64 {
+        as_millis(self.0.borrow().max_frame_time())
+    }
+
+    #[method(name = percentile_)]
+    fn percentile(&self, percent: f64) -> f64 {
+        as_millis(self.0.borrow().percentile(percent))
+    }
+
+    #[method(name = updateTime_)]
+    fn update_time(&self) -> f64 {
+        as_millis(self.0.borrow().update_time())
+    }
+
+    #[method(name = drawTime_)]
+    fn draw_time(&self) -> f64 {
+        as_millis(self.0.borrow().draw_time())
+    }
+}
+
+#[inline]
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_min_max() {
+        let mut stats = FrameStats::new();
+        for ms in &[16, 12, 40, 17] {
+            stats.add_frame(millis(*ms));
+        }
+
+        assert_eq!(stats.frame_count(), 4);
+        assert_eq!(stats.frame_time(), millis(17));
+        assert_eq!(stats.min_frame_time(), millis(12));
+        assert_eq!(stats.max_frame_time(), millis(40));
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let mut stats = FrameStats::new();
+        stats.add_frame(millis(100));
+        for _ in 0..FrameStats::WINDOW_SIZE {
+            stats.add_frame(millis(10));
+        }
+
+        // Slow first frame has left the window.
+        assert_eq!(stats.max_frame_time(), millis(10));
+        assert_eq!(stats.frame_count(), FrameStats::WINDOW_SIZE as u64 + 1);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut stats = FrameStats::new();
+        for ms in 1..=100 {
+            stats.add_frame(millis(ms));
+        }
+
+        assert_eq!(stats.percentile(50.0), millis(50));
+        assert_eq!(stats.percentile(99.0), millis(99));
+        assert_eq!(stats.percentile(100.0), millis(100));
+        assert_eq!(stats.percentile(0.0), millis(1));
+    }
+
+    #[test]
+    fn test_fps() {
+        let mut stats = FrameStats::new();
+        assert_eq!(stats.fps(), 0.0);
+
+        for _ in 0..10 {
+            stats.add_frame(millis(20));
+        }
+        assert!((stats.fps() - 50.0).abs() < 0.001);
+    }
+}
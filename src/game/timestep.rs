@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+/// Accumulator for running updates at a fixed rate, decoupled
+/// from the frame rate.
+///
+/// Frame time is added to the accumulator, and consumed in
+/// fixed sized steps. The left over time, which is less than
+/// one step, is exposed as an interpolation alpha so drawing
+/// can blend between the previous and current state.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// Default limit of catch up steps in a single frame.
+    pub const DEFAULT_MAX_STEPS: u32 = 5;
+
+    /// Shortest step, so the accumulator can always be consumed.
+    pub const MIN_STEP: Duration = Duration::from_nanos(1);
+
+    /// Creates a fixed timestep from a rate given as updates per second.
+    ///
+    /// No more than `max_steps` updates will be run in a single frame.
+    /// Returns `None` when the rate is not a positive number, or is too
+    /// low for the step to be represented.
+    pub fn from_rate(updates_per_second: f64, max_steps: u32) -> Option<Self> {
+        if !updates_per_second.is_finite() || updates_per_second <= 0.0 {
+            return None;
+        }
+
+        let seconds = 1.0 / updates_per_second;
+        if seconds >= u64::MAX as f64 {
+            return None;
+        }
+
+        Some(Self {
+            // Very high rates would round the step down to zero.
+            step: Duration::from_secs_f64(seconds).max(Self::MIN_STEP),
+            max_steps: max_steps.max(1),
+            accumulator: Duration::from_secs(0),
+        })
+    }
+
+    /// Duration of a single update step.
+    #[inline]
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds the frame's time to the accumulator, and returns
+    /// the number of update steps that should be run.
+    ///
+    /// When the game falls too far behind, the time that cannot be
+    /// caught up within `max_steps` is discarded. Otherwise each slow
+    /// frame would cause more updates, making the next frame slower,
+    /// until the game stalls in a spiral of death.
+    pub fn advance(&mut self, delta_time: Duration) -> u32 {
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+
+            if steps >= self.max_steps {
+                // Keep the partial step so interpolation stays smooth.
+                let remainder = self.accumulator.as_nanos() % self.step.as_nanos();
+                self.accumulator = Duration::from_nanos(remainder as u64);
+                break;
+            }
+        }
+
+        steps
+    }
+
+    /// Fraction of a step left in the accumulator, between 0 and 1.
+    #[inline]
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.as_secs_f64() / self.step.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let mut timestep = FixedTimestep::from_rate(10.0, 5).unwrap();
+
+        assert_eq!(timestep.advance(Duration::from_millis(50)), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 0.001);
+
+        assert_eq!(timestep.advance(Duration::from_millis(175)), 2);
+        assert!((timestep.alpha() - 0.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let mut timestep = FixedTimestep::from_rate(10.0, 3).unwrap();
+
+        // Ten steps behind, but only three are allowed.
+        assert_eq!(timestep.advance(Duration::from_millis(1050)), 3);
+        assert!((timestep.alpha() - 0.5).abs() < 0.001);
+
+        // Discarded time is not caught up later.
+        assert_eq!(timestep.advance(Duration::from_millis(0)), 0);
+    }
+
+    #[test]
+    fn test_invalid_rate() {
+        assert!(FixedTimestep::from_rate(0.0, 5).is_none());
+        assert!(FixedTimestep::from_rate(-60.0, 5).is_none());
+        assert!(FixedTimestep::from_rate(f64::NAN, 5).is_none());
+        assert!(FixedTimestep::from_rate(f64::INFINITY, 5).is_none());
+        assert!(FixedTimestep::from_rate(1e-30, 5).is_none());
+    }
+
+    #[test]
+    fn test_min_step() {
+        let mut timestep = FixedTimestep::from_rate(1e15, 3).unwrap();
+        assert_eq!(timestep.step(), FixedTimestep::MIN_STEP);
+        assert_eq!(timestep.advance(Duration::from_millis(1)), 3);
+    }
+}
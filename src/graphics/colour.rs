@@ -16,3 +16,95 @@ impl Rgba8 {
         Self { r, g, b, a }
     }
 }
+
+/// Colour with floating point channels, between 0 and 1.
+#[wren_class]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+#[wren_methods]
+impl Color {
+    #[construct]
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    #[method(name = r_)]
+    fn red(&self) -> f32 {
+        self.r
+    }
+
+    #[method(name = g_)]
+    fn green(&self) -> f32 {
+        self.g
+    }
+
+    #[method(name = b_)]
+    fn blue(&self) -> f32 {
+        self.b
+    }
+
+    #[method(name = a_)]
+    fn alpha(&self) -> f32 {
+        self.a
+    }
+
+    pub fn set(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        *self = Self::new(r, g, b, a);
+    }
+
+    #[method(name = copyFrom)]
+    pub fn copy_from(&mut self, other: &WrenCell<Color>) {
+        *self = self.arg(other);
+    }
+
+    /// Sets this colour to the interpolation between two
+    /// colours, which may include this colour.
+    #[method(name = lerp)]
+    pub fn lerp_wren(&mut self, from: &WrenCell<Color>, to: &WrenCell<Color>, t: f32) {
+        *self = self.arg(from).lerp(&self.arg(to), t);
+    }
+}
+
+impl Color {
+    /// Copies a colour passed to one of this colour's methods.
+    ///
+    /// When the argument is this colour it's already mutably
+    /// borrowed for the call, so this colour is copied instead.
+    fn arg(&self, other: &WrenCell<Color>) -> Color {
+        match other.try_borrow() {
+            Ok(other) => *other,
+            Err(_) => *self,
+        }
+    }
+
+    /// Linear interpolation of each channel.
+    pub fn lerp(&self, to: &Color, t: f32) -> Color {
+        Color {
+            r: self.r + (to.r - self.r) * t,
+            g: self.g + (to.g - self.g) * t,
+            b: self.b + (to.b - self.b) * t,
+            a: self.a + (to.a - self.a) * t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lerp() {
+        let from = Color::new(0.0, 1.0, 0.5, 1.0);
+        let to = Color::new(1.0, 0.0, 0.5, 0.0);
+
+        assert_eq!(from.lerp(&to, 0.0), from);
+        assert_eq!(from.lerp(&to, 1.0), to);
+        assert_eq!(from.lerp(&to, 0.5), Color::new(0.5, 0.5, 0.5, 0.5));
+    }
+}
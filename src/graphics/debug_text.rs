@@ -0,0 +1,280 @@
+//! Minimal text rasteriser for engine diagnostics.
+//!
+//! Renders into a CPU side RGBA image with a built in 8x8
+//! bitmap font, so text can be shown without loading any
+//! assets, for example when scripts have failed.
+
+/// Width and height of a glyph in pixels, before scaling.
+pub const GLYPH_SIZE: u32 = 8;
+
+/// First character in the glyph table.
+const FIRST_CHAR: u32 = 0x20;
+
+/// Glyphs for printable ASCII characters `0x20` to `0x7F`.
+///
+/// Each byte is a row, from top to bottom, and the least
+/// significant bit is the leftmost pixel. Based on the
+/// public domain `font8x8_basic` by Daniel Hepper.
+#[rustfmt::skip]
+const GLYPHS: [[u8; 8]; 96] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // !
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // #
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // $
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // %
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // &
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // (
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // )
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // *
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ,
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // .
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // /
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // 0
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // 1
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // 2
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // 3
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // 4
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // 5
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // 6
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // 7
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // 8
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ;
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // <
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // =
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // >
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // ?
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // @
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // A
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // B
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // C
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // D
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // E
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // F
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // G
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // H
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // I
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // J
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // K
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // L
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // M
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // N
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // O
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // P
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // Q
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // R
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // S
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // T
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // V
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // W
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // X
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // Y
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // Z
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // [
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // \
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ]
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // _
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // a
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // b
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // c
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // d
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // e
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // f
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // g
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // h
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // i
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // j
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // k
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // l
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // m
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // n
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // o
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // p
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // q
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // r
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // s
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // t
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // u
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // v
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // w
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // x
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // y
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // z
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // {
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // |
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // }
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ~
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // DEL
+];
+
+/// RGBA image that text can be drawn into.
+#[derive(Debug)]
+pub struct TextImage {
+    width: u32,
+    height: u32,
+    scale: u32,
+    /// Horizontal position where lines of text are wrapped.
+    wrap_width: u32,
+    pixels: Vec<u8>,
+}
+
+impl TextImage {
+    /// Creates an image filled with the background colour.
+    ///
+    /// Glyphs are scaled by a whole number, so they stay crisp.
+    pub fn new(width: u32, height: u32, scale: u32, background: [u8; 4]) -> Self {
+        let pixels = background
+            .iter()
+            .copied()
+            .cycle()
+            .take(width as usize * height as usize * 4)
+            .collect();
+
+        Self {
+            width,
+            height,
+            scale: scale.max(1),
+            wrap_width: width,
+            pixels,
+        }
+    }
+
+    /// Wrap lines of text before the right edge of the image.
+    pub fn set_wrap_width(&mut self, wrap_width: u32) {
+        self.wrap_width = wrap_width.min(self.width);
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Height of a line of text in pixels, including spacing.
+    #[inline]
+    pub fn line_height(&self) -> u32 {
+        (GLYPH_SIZE + 2) * self.scale
+    }
+
+    /// Draws text with its top left corner at the given position.
+    ///
+    /// Lines are wrapped at the wrap width, and text
+    /// below the bottom edge is cut off. Characters outside of
+    /// printable ASCII are drawn as `?`, and tabs as spaces.
+    ///
+    /// Returns the vertical position below the last drawn line.
+    pub fn draw_text(&mut self, pos: [u32; 2], text: &str, color: [u8; 4]) -> u32 {
+        let advance = GLYPH_SIZE * self.scale;
+        let [left, mut y] = pos;
+
+        for line in text.lines() {
+            let mut x = left;
+
+            for c in line.chars() {
+                if x + advance > self.wrap_width && x > left {
+                    x = left;
+                    y += self.line_height();
+                }
+
+                match c {
+                    '\t' => {
+                        x += advance * 2;
+                        continue;
+                    }
+                    ' '..='~' => self.draw_glyph([x, y], c as u32, color),
+                    _ => self.draw_glyph([x, y], '?' as u32, color),
+                }
+
+                x += advance;
+            }
+
+            y += self.line_height();
+        }
+
+        y
+    }
+
+    fn draw_glyph(&mut self, pos: [u32; 2], code: u32, color: [u8; 4]) {
+        let glyph = &GLYPHS[(code - FIRST_CHAR) as usize];
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..GLYPH_SIZE {
+                if bits & (1 << col) == 0 {
+                    continue;
+                }
+
+                let x = pos[0] + col * self.scale;
+                let y = pos[1] + row as u32 * self.scale;
+                self.fill_rect([x, y], self.scale, color);
+            }
+        }
+    }
+
+    fn fill_rect(&mut self, pos: [u32; 2], size: u32, color: [u8; 4]) {
+        for y in pos[1]..(pos[1] + size).min(self.height) {
+            for x in pos[0]..(pos[0] + size).min(self.width) {
+                let index = (y as usize * self.width as usize + x as usize) * 4;
+                self.pixels[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+    fn pixel(image: &TextImage, x: u32, y: u32) -> [u8; 4] {
+        let index = (y * image.width + x) as usize * 4;
+        let mut color = [0; 4];
+        color.copy_from_slice(&image.pixels()[index..index + 4]);
+        color
+    }
+
+    #[test]
+    fn test_draw_glyph() {
+        let mut image = TextImage::new(16, 16, 2, BLACK);
+        image.draw_text([0, 0], "|", WHITE);
+
+        // Bar of the pipe character spans columns 3 and 4, scaled by 2.
+        assert_eq!(pixel(&image, 6, 0), WHITE);
+        assert_eq!(pixel(&image, 9, 0), WHITE);
+        assert_eq!(pixel(&image, 5, 0), BLACK);
+        assert_eq!(pixel(&image, 10, 0), BLACK);
+    }
+
+    #[test]
+    fn test_wrap_lines() {
+        let mut image = TextImage::new(20, 100, 1, BLACK);
+        let line_height = image.line_height();
+
+        // Two glyphs fit on a line.
+        assert_eq!(image.draw_text([0, 0], "abc", WHITE), line_height * 2);
+        assert_eq!(image.draw_text([0, 0], "a\nb", WHITE), line_height * 2);
+        assert_eq!(image.draw_text([0, 0], "ab", WHITE), line_height);
+    }
+
+    #[test]
+    fn test_wrap_width() {
+        let mut image = TextImage::new(100, 100, 1, BLACK);
+        image.set_wrap_width(20);
+        assert_eq!(image.draw_text([0, 0], "abc", WHITE), image.line_height() * 2);
+    }
+
+    #[test]
+    fn test_clip() {
+        // Must not panic when text runs off the image.
+        let mut image = TextImage::new(10, 10, 3, BLACK);
+        image.draw_text([0, 0], "Error\n\u{1F4A5}\nline\nline", WHITE);
+    }
+}
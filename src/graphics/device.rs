@@ -3,14 +3,16 @@ use crate::{
         errors::debug_assert_gl, shader::Shader, texture::Texture, transform::Transform2D, vao::VertexArrayObject,
         GRAPHICS_MODULE,
     },
+    io::BuiltinModules,
     marker::Invariant,
+    profiler,
 };
 use glow::HasContext;
 use glutin::{dpi::PhysicalSize, PossiblyCurrent, WindowedContext};
 use rust_wren::{
     handle::{FnSymbolRef, WrenCallHandle, WrenCallRef},
     prelude::*,
-    ModuleBuilder, WrenContext, WrenResult, WrenVm,
+    ModuleBuilder, WrenContext,
 };
 use std::{
     cell::{Cell, RefCell},
@@ -51,8 +53,8 @@ pub fn init_graphic_device(ctx: &mut WrenContext, device: GraphicDevice) -> Grap
     }
 }
 
-pub fn register_graphic_device(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret(GRAPHICS_MODULE, include_str!("device.wren"))
+pub fn register_graphic_device(builtins: &mut BuiltinModules) {
+    builtins.add(GRAPHICS_MODULE, include_str!("device.wren"));
 }
 
 pub fn bind_graphic_device(module: &mut ModuleBuilder) {
@@ -61,7 +63,9 @@ pub fn bind_graphic_device(module: &mut ModuleBuilder) {
 
 #[wren_class]
 pub struct GraphicDevice {
-    pub(crate) gl: glow::Context,
+    /// OpenGL context. Is `None` when the device is headless, in which
+    /// case draw calls are no-ops.
+    gl: Option<glow::Context>,
     extensions: HashSet<String>,
     destroy: DestroyQueue,
     viewport_size: Cell<PhysicalSize<u32>>,
@@ -163,7 +167,7 @@ impl GraphicDevice {
         }
 
         Self {
-            gl,
+            gl: Some(gl),
             extensions,
             // Dropped resources need to be deallocated via the OpenGL context.
             destroy: Default::default(),
@@ -172,6 +176,21 @@ impl GraphicDevice {
         }
     }
 
+    /// Creates a graphic device without an OpenGL context.
+    ///
+    /// Resources are allocated with placeholder handles and
+    /// draw calls do nothing, so scripts can run unchanged on
+    /// machines without a display.
+    pub fn headless(viewport_size: PhysicalSize<u32>) -> Self {
+        Self {
+            gl: None,
+            extensions: HashSet::new(),
+            destroy: Default::default(),
+            viewport_size: Cell::new(viewport_size),
+            _invariant: Default::default(),
+        }
+    }
+
     /// Creates a graphic device from the given `glutin::WindowedContext`.
     pub unsafe fn from_windowed_context(windowed_context: &WindowedContext<PossiblyCurrent>) -> Self {
         // Glue glow and glutin together.
@@ -183,6 +202,17 @@ impl GraphicDevice {
         device
     }
 
+    /// Borrows the OpenGL context, or `None` when the device is headless.
+    #[inline]
+    pub(crate) fn gl(&self) -> Option<&glow::Context> {
+        self.gl.as_ref()
+    }
+
+    #[inline]
+    pub fn is_headless(&self) -> bool {
+        self.gl.is_none()
+    }
+
     #[inline]
     pub fn viewport_size(&self) -> PhysicalSize<u32> {
         self.viewport_size.get()
@@ -195,16 +225,20 @@ impl GraphicDevice {
 
     #[inline]
     pub fn clear_screen(&self, color: [f32; 4]) {
+        let gl = match self.gl() {
+            Some(gl) => gl,
+            None => return,
+        };
+
         unsafe {
             let physical_size_i32 = self.viewport_size.get().cast::<i32>();
 
             // Tell openGL how to map normalised device coordinates to pixels.
-            self.gl
-                .viewport(0, 0, physical_size_i32.width, physical_size_i32.height);
+            gl.viewport(0, 0, physical_size_i32.width, physical_size_i32.height);
 
-            self.gl.clear_color(color[0], color[1], color[2], color[3]);
-            self.gl.clear(glow::COLOR_BUFFER_BIT);
-            debug_assert_gl(&self.gl, ());
+            gl.clear_color(color[0], color[1], color[2], color[3]);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+            debug_assert_gl(gl, ());
         }
     }
 
@@ -215,6 +249,11 @@ impl GraphicDevice {
         shader: &Shader,
         trans: &Transform2D,
     ) {
+        let gl = match self.gl() {
+            Some(gl) => gl,
+            None => return,
+        };
+
         // TODO: This drawing code may have to live in the render target.
         let canvas_size = self.viewport_size.get();
 
@@ -223,65 +262,66 @@ impl GraphicDevice {
 
             // Viewport tells OpenGL how to map normalised device coordinates
             // to pixels for rasterisation.
-            self.gl
-                .viewport(0, 0, physical_size_i32.width, physical_size_i32.height);
+            gl.viewport(0, 0, physical_size_i32.width, physical_size_i32.height);
 
-            self.gl.use_program(Some(shader.program));
+            gl.use_program(Some(shader.program));
 
             // FIXME: Specific to the sprite shader.
             //        Shader should be flexible enough
             //        for uniforms to be specified
             //        at runtime.
-            self.gl
-                .uniform_2_f32(Some(&0), canvas_size.width as f32, canvas_size.height as f32);
+            gl.uniform_2_f32(Some(&0), canvas_size.width as f32, canvas_size.height as f32);
 
             let matrix = trans.to_matrix4();
             let matrix_data = matrix.as_slice();
-            self.gl.uniform_matrix_4_f32_slice(Some(&1), false, matrix_data);
+            gl.uniform_matrix_4_f32_slice(Some(&1), false, matrix_data);
         }
 
         // Draw call
         unsafe {
-            self.gl.bind_vertex_array(Some(vao.vao));
+            gl.bind_vertex_array(Some(vao.vao));
 
             // Map uniform sampler to texture unit.
             // - First argument is the uniform's location in the shader.
             // - Second argument is the texture unit eg. `TEXTURE0`
-            self.gl.uniform_1_i32(Some(&2), 0);
+            gl.uniform_1_i32(Some(&2), 0);
 
             // TODO: Which textures, and how many, are bound
             //       should be determined at runtime by
             //       a material and pipeline.
-            self.gl.active_texture(glow::TEXTURE0);
-            self.gl.bind_texture(glow::TEXTURE_2D, Some(tex.raw_handle()));
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(tex.raw_handle()));
 
             // FIXME: Unsigned short is a detail of the vertex buffer, so drawing should probably happen there.
-            self.gl
-                .draw_elements(glow::TRIANGLES, vao.len() as i32, glow::UNSIGNED_SHORT, 0);
-            debug_assert_gl(&self.gl, ());
+            gl.draw_elements(glow::TRIANGLES, vao.len() as i32, glow::UNSIGNED_SHORT, 0);
+            debug_assert_gl(gl, ());
         }
 
         // Cleanup
         unsafe {
-            self.gl.bind_vertex_array(None);
-            self.gl.use_program(None);
+            gl.bind_vertex_array(None);
+            gl.use_program(None);
         }
     }
 
     /// Query the graphics driver for hardware information.
+    ///
+    /// Returns `None` when the device is headless.
     #[inline]
-    pub fn opengl_info(&self) -> OpenGlInfo {
+    pub fn opengl_info(&self) -> Option<OpenGlInfo> {
+        let gl = self.gl()?;
+
         unsafe {
-            let version = self.gl.get_parameter_string(glow::VERSION);
-            let vendor = self.gl.get_parameter_string(glow::VENDOR);
-            let renderer = self.gl.get_parameter_string(glow::RENDERER);
-            debug_assert_gl(&self.gl, ());
+            let version = gl.get_parameter_string(glow::VERSION);
+            let vendor = gl.get_parameter_string(glow::VENDOR);
+            let renderer = gl.get_parameter_string(glow::RENDERER);
+            debug_assert_gl(gl, ());
 
-            OpenGlInfo {
+            Some(OpenGlInfo {
                 version,
                 vendor,
                 renderer,
-            }
+            })
         }
     }
 
@@ -290,21 +330,40 @@ impl GraphicDevice {
         self.destroy.clone()
     }
 
+    /// Shares the release queue of another device on the same OpenGL
+    /// context, so resources dropped after that device is gone are
+    /// still released, by this one.
+    pub(crate) fn with_destroy_queue(mut self, destroy: DestroyQueue) -> Self {
+        self.destroy = destroy;
+        self
+    }
+
     /// Release graphics resources.
     pub fn maintain(&self) {
+        let _zone = profiler::zone("GraphicDevice::maintain");
+
+        let gl = match self.gl() {
+            Some(gl) => gl,
+            None => {
+                // Headless resources have no video memory to release.
+                self.destroy.0.borrow_mut().clear();
+                return;
+            }
+        };
+
         for resource in self.destroy.0.borrow_mut().drain(..) {
             match resource {
                 Destroy::Texture(handle) => unsafe {
                     log::debug!("destroying texture");
-                    self.gl.delete_texture(handle);
+                    gl.delete_texture(handle);
                 },
                 Destroy::Shader(program) => unsafe {
                     log::debug!("destroying texture");
-                    self.gl.delete_program(program);
+                    gl.delete_program(program);
                 },
                 Destroy::VertexArray(handle) => unsafe {
                     log::debug!("destroying texture");
-                    self.gl.delete_vertex_array(handle);
+                    gl.delete_vertex_array(handle);
                 },
             }
         }
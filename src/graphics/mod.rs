@@ -1,5 +1,6 @@
 mod angle;
 mod colour;
+mod debug_text;
 mod device;
 mod errors;
 mod rect;
@@ -14,10 +15,12 @@ mod vertex;
 mod vertex_array;
 
 pub const GRAPHICS_MODULE: &str = "gers.graphics";
+pub use self::colour::Color;
+pub use self::debug_text::TextImage;
 pub use self::device::{
     bind_graphic_device, init_graphic_device, register_graphic_device, GraphicDevice, GraphicDeviceHooks, OpenGlInfo,
 };
-pub use self::errors::GfxError;
+pub use self::errors::{GfxError, GfxResult};
 pub use self::shader::{init_default_shaders, Shader};
 pub use self::sprite_batch::SpriteBatch;
 pub use self::texture::Texture;
@@ -26,23 +29,24 @@ pub use self::vao::{UsageFrequency, UsageNature, VertexArrayObject};
 pub use self::vertex::Vertex;
 pub use self::vertex_array::VertexArray;
 
-use rust_wren::{prelude::*, ModuleBuilder, WrenResult};
+use crate::io::BuiltinModules;
+use rust_wren::ModuleBuilder;
 
-pub fn register_graphics(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret(GRAPHICS_MODULE, include_str!("transform.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("vertex.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("vao.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("vertex_array.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("texture.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("sprite.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("sprite_batch.wren"))?;
-    vm.interpret(GRAPHICS_MODULE, include_str!("shader.wren"))?;
-
-    Ok(())
+pub fn register_graphics(builtins: &mut BuiltinModules) {
+    builtins.add(GRAPHICS_MODULE, include_str!("transform.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("colour.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("vertex.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("vao.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("vertex_array.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("texture.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("sprite.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("sprite_batch.wren"));
+    builtins.add(GRAPHICS_MODULE, include_str!("shader.wren"));
 }
 
 pub fn bind_graphics(module: &mut ModuleBuilder) {
     module.register::<Transform2D>();
+    module.register::<Color>();
     module.register::<Vertex>();
     module.register::<VertexArrayObject>();
     module.register::<VertexArray>();
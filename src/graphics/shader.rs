@@ -11,9 +11,7 @@ use rust_wren::{prelude::*, ForeignError, WrenContext, WrenError, WrenResult};
 pub fn init_default_shaders(ctx: &mut WrenContext, device: &GraphicDevice) -> WrenResult<()> {
     {
         // Default sprite shader
-        let vert = include_str!("shaders/sprite.vert");
-        let frag = include_str!("shaders/sprite.frag");
-        let shader = Shader::from_source(device, vert, frag).map_err(|err| WrenError::Ctx(err.into()))?;
+        let shader = Shader::sprite(device).map_err(|err| WrenError::Ctx(err.into()))?;
 
         let default_prop = ctx.make_call_ref(GRAPHICS_MODULE, "Shader", "default_=(_)")?;
         default_prop.call::<_, ()>(ctx, shader)?;
@@ -42,10 +40,28 @@ impl Shader {
 }
 
 impl Shader {
+    /// Compiles the built in shader used to draw sprites.
+    pub fn sprite(device: &GraphicDevice) -> GfxResult<Self> {
+        let vert = include_str!("shaders/sprite.vert");
+        let frag = include_str!("shaders/sprite.frag");
+        Self::from_source(device, vert, frag)
+    }
+
     pub fn from_source(device: &GraphicDevice, vertex: &str, fragment: &str) -> GfxResult<Self> {
+        let gl = match device.gl() {
+            Some(gl) => gl,
+            // Headless device has nothing to compile.
+            None => {
+                return Ok(Self {
+                    program: 0,
+                    destroy: device.destroy_queue(),
+                })
+            }
+        };
+
         // Create Shader program.
         // Call is infallible.
-        let program = unsafe { device.gl.create_program().unwrap() };
+        let program = unsafe { gl.create_program().unwrap() };
 
         // Link shaders.
         let shader_sources = [(glow::VERTEX_SHADER, vertex), (glow::FRAGMENT_SHADER, fragment)];
@@ -54,29 +70,29 @@ impl Shader {
 
         for (shader_type, shader_source) in shader_sources.iter() {
             unsafe {
-                let shader = device.gl.create_shader(*shader_type).unwrap();
-                device.gl.shader_source(shader, shader_source);
-                device.gl.compile_shader(shader);
-                if !device.gl.get_shader_compile_status(shader) {
-                    return Err(GfxError::ShaderCompile(device.gl.get_shader_info_log(shader)));
+                let shader = gl.create_shader(*shader_type).unwrap();
+                gl.shader_source(shader, shader_source);
+                gl.compile_shader(shader);
+                if !gl.get_shader_compile_status(shader) {
+                    return Err(GfxError::ShaderCompile(gl.get_shader_info_log(shader)));
                 }
-                device.gl.attach_shader(program, shader);
+                gl.attach_shader(program, shader);
                 shaders.push(shader);
             }
         }
 
         unsafe {
-            device.gl.link_program(program);
-            if !device.gl.get_program_link_status(program) {
-                return Err(GfxError::ShaderCompile(device.gl.get_program_info_log(program)));
+            gl.link_program(program);
+            if !gl.get_program_link_status(program) {
+                return Err(GfxError::ShaderCompile(gl.get_program_info_log(program)));
             }
         }
 
         // Once the shaders are linked to a program, it's safe to detach and delete them.
         for shader in shaders {
             unsafe {
-                device.gl.detach_shader(program, shader);
-                device.gl.delete_shader(shader);
+                gl.detach_shader(program, shader);
+                gl.delete_shader(shader);
             }
         }
 
@@ -1,13 +1,17 @@
 //! Batch sprites together for optimised draw calls.
-use crate::graphics::{
-    device::GraphicDevice,
-    errors::{debug_assert_gl, GfxResult},
-    shader::Shader,
-    texture::{Texture, TextureHandle},
-    transform::Transform2D,
-    utils,
-    vao::{UsageFrequency, UsageNature, VertexArrayObject},
-    vertex::Vertex,
+use crate::{
+    ecs::WrenWorld,
+    graphics::{
+        device::GraphicDevice,
+        errors::{debug_assert_gl, GfxResult},
+        shader::Shader,
+        texture::{Texture, TextureHandle},
+        transform::Transform2D,
+        utils,
+        vao::{UsageFrequency, UsageNature, VertexArrayObject},
+        vertex::Vertex,
+    },
+    profiler,
 };
 use glow::HasContext;
 use nalgebra::Point3;
@@ -45,17 +49,16 @@ impl SpriteBatch {
         transform: &WrenCell<Transform2D>,
     ) {
         if let Some(texture) = texture.and_then(|t| t.try_borrow().ok()) {
-            // Copies stuff needed for drawing to the internal batch item buffer.
-            self.items.push(BatchItem {
-                pos: [x, y],
-                size: [width, height],
-                texture_raw: texture.raw_handle(),
-                texture_handle: texture.handle(),
-                transform: transform.borrow().clone(),
-            });
+            self.add(x, y, width, height, &*texture, transform.borrow().clone());
         }
     }
 
+    /// Adds the sprites of all entities in an ECS world.
+    #[method(name = addEntities_)]
+    fn add_entities(&mut self, world: &WrenCell<WrenWorld>) {
+        world.borrow().submit_sprites(self);
+    }
+
     #[method(name = draw)]
     fn draw_2(&mut self, device: &WrenCell<GraphicDevice>, shader: &WrenCell<Shader>) -> Result<(), ForeignError> {
         self.draw(&*device.borrow(), &*shader.borrow(), &Transform2D::default())
@@ -111,6 +114,18 @@ impl SpriteBatch {
         })
     }
 
+    /// Queues a sprite to be drawn on the next call to `draw`.
+    pub fn add(&mut self, x: f32, y: f32, width: f32, height: f32, texture: &Texture, transform: Transform2D) {
+        // Copies stuff needed for drawing to the internal batch item buffer.
+        self.items.push(BatchItem {
+            pos: [x, y],
+            size: [width, height],
+            texture_raw: texture.raw_handle(),
+            texture_handle: texture.handle(),
+            transform,
+        });
+    }
+
     #[allow(clippy::many_single_char_names)]
     pub fn draw(&mut self, device: &GraphicDevice, shader: &Shader, transform: &Transform2D) -> GfxResult<()> {
         // Nothing to draw.
@@ -118,28 +133,33 @@ impl SpriteBatch {
             return Ok(());
         }
 
+        let gl = match device.gl() {
+            Some(gl) => gl,
+            None => {
+                // Headless device discards the batch.
+                self.items.clear();
+                return Ok(());
+            }
+        };
+
         unsafe {
             let canvas_size = device.viewport_size();
 
             let physical_size_i32 = canvas_size.cast::<i32>();
-            device
-                .gl
-                .viewport(0, 0, physical_size_i32.width, physical_size_i32.height);
+            gl.viewport(0, 0, physical_size_i32.width, physical_size_i32.height);
 
-            device.gl.use_program(Some(shader.program));
+            gl.use_program(Some(shader.program));
 
             // FIXME: Specific to the sprite shader.
-            device
-                .gl
-                .uniform_2_f32(Some(&0), canvas_size.width as f32, canvas_size.height as f32);
+            gl.uniform_2_f32(Some(&0), canvas_size.width as f32, canvas_size.height as f32);
 
             let matrix = transform.to_matrix4();
             let matrix_data = matrix.as_slice();
             // FIXME: Uniform location specific to the sprite shader.
-            device.gl.uniform_matrix_4_f32_slice(Some(&1), false, matrix_data);
-            // device.gl.uniform_matrix_4_f32_slice(Some(&1), false, Transform2D::default().to_matrix4().as_slice());
+            gl.uniform_matrix_4_f32_slice(Some(&1), false, matrix_data);
+            // gl.uniform_matrix_4_f32_slice(Some(&1), false, Transform2D::default().to_matrix4().as_slice());
 
-            device.gl.bind_vertex_array(Some(self.vao.vao));
+            gl.bind_vertex_array(Some(self.vao.vao));
         }
 
         let SpriteBatch {
@@ -156,7 +176,7 @@ impl SpriteBatch {
             // log::info!("### BATCH {} ###", batch_count);
 
             if batch_count >= Self::BATCH_SIZE {
-                Self::flush(device, vao, &vertices, &indices);
+                Self::flush(gl, vao, &vertices, &indices);
                 vertices.clear();
                 indices.clear();
                 batch_count = 0;
@@ -164,7 +184,7 @@ impl SpriteBatch {
 
             // The buffer is flushed each time we encounter a new texture.
             if last_texture != Some(item.texture_raw) {
-                Self::flush(device, vao, &vertices, &indices);
+                Self::flush(gl, vao, &vertices, &indices);
                 vertices.clear();
                 indices.clear();
                 batch_count = 0;
@@ -173,11 +193,11 @@ impl SpriteBatch {
                     // Map uniform sampler to texture unit.
                     // - First argument is the uniform's location in the shader.
                     // - Second argument is the texture unit eg. `TEXTURE0`
-                    device.gl.uniform_1_i32(Some(&2), 0);
+                    gl.uniform_1_i32(Some(&2), 0);
 
                     // Texture slot determined by sprite shader.
-                    device.gl.active_texture(glow::TEXTURE0);
-                    device.gl.bind_texture(glow::TEXTURE_2D, Some(item.texture_raw));
+                    gl.active_texture(glow::TEXTURE0);
+                    gl.bind_texture(glow::TEXTURE_2D, Some(item.texture_raw));
                 }
             }
 
@@ -267,27 +287,29 @@ impl SpriteBatch {
 
         // Flush the last sprites that didn't reach the threshold.
         if batch_count > 0 {
-            Self::flush(device, vao, &vertices, &indices);
+            Self::flush(gl, vao, &vertices, &indices);
             vertices.clear();
             indices.clear();
         }
 
         unsafe {
-            device.gl.bind_texture(glow::TEXTURE_2D, None);
-            device.gl.bind_vertex_array(None);
-            device.gl.use_program(None);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            gl.bind_vertex_array(None);
+            gl.use_program(None);
         }
 
         Ok(())
     }
 
     /// This is where the actual drawing happens.
-    fn flush(device: &GraphicDevice, vao: &VertexArrayObject, vertices: &[Vertex], indices: &[u16]) {
+    fn flush(gl: &glow::Context, vao: &VertexArrayObject, vertices: &[Vertex], indices: &[u16]) {
         if vertices.is_empty() {
             // Nothing to draw
             return;
         }
 
+        let _zone = profiler::zone("SpriteBatch::flush").arg("sprites", vertices.len() / 4);
+
         // println!("{:?} {:?} {:?} {:?}", vertices[0].position, vertices[1].position, vertices[2].position, vertices[3].position);
         // println!("{:?}", indices);
 
@@ -295,25 +317,17 @@ impl SpriteBatch {
 
         unsafe {
             // Upload new data.
-            device.gl.bind_buffer(glow::ARRAY_BUFFER, Some(vao.vertex_buf_handle()));
-            device
-                .gl
-                .buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, &utils::as_u8(vertices));
-            debug_assert_gl(&device.gl, ());
-
-            device
-                .gl
-                .bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(vao.index_buf_handle()));
-            device
-                .gl
-                .buffer_sub_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, 0, &utils::as_u8(indices));
-            debug_assert_gl(&device.gl, ());
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vao.vertex_buf_handle()));
+            gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, &utils::as_u8(vertices));
+            debug_assert_gl(gl, ());
+
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(vao.index_buf_handle()));
+            gl.buffer_sub_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, 0, &utils::as_u8(indices));
+            debug_assert_gl(gl, ());
 
             // FIXME: Unsigned short is a detail of the vertex buffer, so drawing should probably happen there.
-            device
-                .gl
-                .draw_elements(glow::TRIANGLES, indices.len() as i32, glow::UNSIGNED_SHORT, 0);
-            debug_assert_gl(&device.gl, ());
+            gl.draw_elements(glow::TRIANGLES, indices.len() as i32, glow::UNSIGNED_SHORT, 0);
+            debug_assert_gl(gl, ());
         }
     }
 }
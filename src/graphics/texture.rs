@@ -6,6 +6,7 @@ use crate::{
         errors::{debug_assert_gl, GfxError, GfxResult},
         rect::Rect,
     },
+    io::read_asset,
     marker::Invariant,
 };
 use glow::HasContext;
@@ -15,6 +16,7 @@ use std::{fmt, rc::Rc};
 
 /// Handle to a texture located in video memory.
 #[wren_class]
+#[derive(Clone)]
 pub struct Texture {
     /// Handle to texture allocated in video memory.
     ///
@@ -53,7 +55,8 @@ impl Texture {
     #[method(name = fromFile)]
     fn from_file(device: &WrenCell<GraphicDevice>, filepath: &str) -> Result<Self, ForeignError> {
         let device = &*device.borrow();
-        let img = image::open(filepath).map_err(|err| foreign_error!(err))?;
+        let bytes = read_asset(filepath).map_err(|err| foreign_error!(err))?;
+        let img = image::load_from_memory(&bytes).map_err(|err| foreign_error!(err))?;
 
         log::info!("Loaded image: {}", filepath);
         log::info!("  Dimensions: {:?}", img.dimensions());
@@ -91,6 +94,12 @@ impl Texture {
         // Upfront validations.
         Self::validate_size(width, height)?;
 
+        let gl = match device.gl() {
+            Some(gl) => gl,
+            // Headless device has no video memory.
+            None => return Ok(Self::from_raw_handle(device, 0, width, height)),
+        };
+
         // When non-power-of-two textures are not available, several
         // bad things can happen from degraded performance to OpenGL
         // errors.
@@ -104,12 +113,12 @@ impl Texture {
 
         unsafe {
             // Create texture returns `Result` but error should be `Infallible`.
-            let handle = device.gl.create_texture().unwrap();
-            gl_result!(&device.gl);
-            device.gl.bind_texture(glow::TEXTURE_2D, Some(handle));
+            let handle = gl.create_texture().unwrap();
+            gl_result!(gl);
+            gl.bind_texture(glow::TEXTURE_2D, Some(handle));
 
             // Allocate video memory for texture
-            device.gl.tex_image_2d(
+            gl.tex_image_2d(
                 glow::TEXTURE_2D,
                 0,                   // Mip level
                 glow::RGBA8 as i32,  // Internal colour format
@@ -121,42 +130,40 @@ impl Texture {
                 None,                // Actual data can be uploaded later.
             );
             // gl_error(&device.gl, ())?;
-            gl_result!(device.gl);
-
-            device
-                .gl
-                .tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
-            device
-                .gl
-                .tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
-            device
-                .gl
-                .tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
-            device
-                .gl
-                .tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
-            device.gl.bind_texture(glow::TEXTURE_2D, None);
-
-            // Match the allocated texture.
-            let rect = Rect {
-                pos: [0, 0],
-                size: [width, height],
-            };
-
-            let texture_handle = TextureHandle {
-                handle,
-                size: [width, height],
-                destroy: device.destroy_queue(),
-                _invariant: Default::default(),
-            };
-
-            Ok(Self {
-                texture: handle,
-                rect,
-                handle: Rc::new(texture_handle),
-                // destroy: device.destroy_queue(),
-                // _invariant: Default::default(),
-            })
+            gl_result!(gl);
+
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            Ok(Self::from_raw_handle(device, handle, width, height))
+        }
+    }
+
+    /// Wraps an allocated texture handle, with a view
+    /// matching the whole texture.
+    fn from_raw_handle(device: &GraphicDevice, handle: glow::Texture, width: u32, height: u32) -> Self {
+        // Match the allocated texture.
+        let rect = Rect {
+            pos: [0, 0],
+            size: [width, height],
+        };
+
+        let texture_handle = TextureHandle {
+            handle,
+            size: [width, height],
+            destroy: device.destroy_queue(),
+            _invariant: Default::default(),
+        };
+
+        Self {
+            texture: handle,
+            rect,
+            handle: Rc::new(texture_handle),
+            // destroy: device.destroy_queue(),
+            // _invariant: Default::default(),
         }
     }
 
@@ -262,15 +269,20 @@ impl Texture {
             });
         }
 
+        let gl = match device.gl() {
+            Some(gl) => gl,
+            None => return Ok(()),
+        };
+
         // Borrow mut to enforce runtime borrow rules.
         // TODO: Do we need Texture to have an internal RefCell?
         // let handle = self.handle.borrow_mut();
 
         unsafe {
-            let _save = TextureSave::new(&device);
+            let _save = TextureSave::new(gl);
 
-            device.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
-            device.gl.tex_sub_image_2d(
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.tex_sub_image_2d(
                 glow::TEXTURE_2D,
                 0,                   // level
                 pos[0] as i32,       // x_offset
@@ -281,7 +293,7 @@ impl Texture {
                 glow::UNSIGNED_BYTE, // color data type
                 glow::PixelUnpackData::Slice(data),
             );
-            gl_result!(&device.gl);
+            gl_result!(gl);
         }
 
         Ok(())
@@ -357,12 +369,10 @@ pub(crate) struct TextureSave<'a> {
 }
 
 impl<'a> TextureSave<'a> {
-    pub(crate) fn new(device: &'a GraphicDevice) -> Self {
+    pub(crate) fn new(gl: &'a glow::Context) -> Self {
         Self {
-            gl: &device.gl,
-            texture_handle: unsafe {
-                debug_assert_gl(&device.gl, device.gl.get_parameter_i32(glow::TEXTURE_BINDING_2D) as u32)
-            },
+            gl,
+            texture_handle: unsafe { debug_assert_gl(gl, gl.get_parameter_i32(glow::TEXTURE_BINDING_2D) as u32) },
         }
     }
 }
@@ -58,9 +58,43 @@ impl Transform2D {
     pub fn rotate(&mut self, degrees: f32) {
         self.rotation.0 += Deg(degrees).as_radians();
     }
+
+    #[method(name = copyFrom)]
+    pub fn copy_from(&mut self, other: &WrenCell<Transform2D>) {
+        *self = self.arg(other);
+    }
+
+    /// Sets this transform to the interpolation between two
+    /// transforms, which may include this transform.
+    #[method(name = lerp)]
+    pub fn lerp_wren(&mut self, from: &WrenCell<Transform2D>, to: &WrenCell<Transform2D>, t: f32) {
+        *self = self.arg(from).lerp(&self.arg(to), t);
+    }
 }
 
 impl Transform2D {
+    /// Copies a transform passed to one of this transform's methods.
+    ///
+    /// When the argument is this transform it's already mutably
+    /// borrowed for the call, so this transform is copied instead.
+    fn arg(&self, other: &WrenCell<Transform2D>) -> Transform2D {
+        match other.try_borrow() {
+            Ok(other) => other.clone(),
+            Err(_) => self.clone(),
+        }
+    }
+
+    /// Linear interpolation of each component. The rotation is
+    /// interpolated in radians, without wrapping around.
+    pub fn lerp(&self, to: &Transform2D, t: f32) -> Transform2D {
+        Transform2D {
+            position: self.position + (to.position - self.position) * t,
+            offset: self.offset.lerp(&to.offset, t),
+            scale: self.scale.lerp(&to.scale, t),
+            rotation: Rad(self.rotation.0 + (to.rotation.0 - self.rotation.0) * t),
+        }
+    }
+
     /// Create a matrix from the transform suitable to
     /// be passed to a shader.
     ///
@@ -89,4 +123,19 @@ impl Transform2D {
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lerp() {
+        let mut to = Transform2D::new();
+        to.set_pos(10.0, -20.0);
+        to.set_scale(3.0, 3.0);
+        to.rotate(90.0);
+
+        let half = Transform2D::new().lerp(&to, 0.5);
+        assert_eq!(half.position, Point2::new(5.0, -10.0));
+        assert_eq!(half.scale, Vector2::new(2.0, 2.0));
+        assert!((half.rotation.as_radians() - Deg(45.0_f32).as_radians()).abs() < 1e-6);
+    }
+}
@@ -87,25 +87,37 @@ impl VertexArrayObject {
             }
         }
 
+        let gl = match device.gl() {
+            Some(gl) => gl,
+            // Headless device has no video memory.
+            None => {
+                return Ok(Self {
+                    vao: 0,
+                    vertex_buffer: 0,
+                    index_buffer: 0,
+                    size: indices.len(),
+                    destroy: device.destroy_queue(),
+                })
+            }
+        };
+
         unsafe {
             // Vertex Buffer Object
-            let vertex_array = device.gl.create_vertex_array().unwrap();
-            device.gl.bind_vertex_array(Some(vertex_array));
+            let vertex_array = gl.create_vertex_array().unwrap();
+            gl.bind_vertex_array(Some(vertex_array));
 
             // Attached buffer space
-            let vertex_buffer = device.gl.create_buffer().unwrap();
-            device.gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
-            device
-                .gl
-                .buffer_data_u8_slice(glow::ARRAY_BUFFER, utils::as_u8(vertices), Self::mem_hint(freq, nat));
+            let vertex_buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, utils::as_u8(vertices), Self::mem_hint(freq, nat));
             // assert_gl(&device.gl);
-            gl_result!(device.gl);
+            gl_result!(gl);
 
             // Vertex data is interleaved.
             // Attribute layout positions are determined by shader.
             // Positions
-            device.gl.enable_vertex_attrib_array(Self::POSITION_LOC);
-            device.gl.vertex_attrib_pointer_f32(
+            gl.enable_vertex_attrib_array(Self::POSITION_LOC);
+            gl.vertex_attrib_pointer_f32(
                 Self::POSITION_LOC,                             // Attribute location in shader program.
                 2,                                              // Size. Components per iteration.
                 glow::FLOAT,                                    // Type to get from buffer.
@@ -114,11 +126,11 @@ impl VertexArrayObject {
                 memoffset::offset_of!(Vertex, position) as i32, // Offset. Bytes from start of buffer.
             );
             // assert_gl(&device.gl);
-            gl_result!(device.gl);
+            gl_result!(gl);
 
             // UVs
-            device.gl.enable_vertex_attrib_array(Self::UV_LOC);
-            device.gl.vertex_attrib_pointer_f32(
+            gl.enable_vertex_attrib_array(Self::UV_LOC);
+            gl.vertex_attrib_pointer_f32(
                 Self::UV_LOC,                             // Attribute location in shader program.
                 2,                                        // Size. Components per iteration.
                 glow::FLOAT,                              // Type to get from buffer.
@@ -127,11 +139,11 @@ impl VertexArrayObject {
                 memoffset::offset_of!(Vertex, uv) as i32, // Offset. Bytes from start of buffer.
             );
             // assert_gl(&device.gl);
-            gl_result!(device.gl);
+            gl_result!(gl);
 
             // Colors
-            device.gl.enable_vertex_attrib_array(Self::COLOR_LOC);
-            device.gl.vertex_attrib_pointer_f32(
+            gl.enable_vertex_attrib_array(Self::COLOR_LOC);
+            gl.vertex_attrib_pointer_f32(
                 Self::COLOR_LOC,                             // Attribute location in shader program.
                 4,                                           // Size. Components per iteration.
                 glow::FLOAT,                                 // Type to get from buffer.
@@ -140,20 +152,20 @@ impl VertexArrayObject {
                 memoffset::offset_of!(Vertex, color) as i32, // Offset. Bytes from start of buffer.
             );
             // assert_gl(&device.gl);
-            gl_result!(device.gl);
+            gl_result!(gl);
 
             // Indices
-            let index_buffer = device.gl.create_buffer().unwrap();
-            device.gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
-            device.gl.buffer_data_u8_slice(
+            let index_buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+            gl.buffer_data_u8_slice(
                 glow::ELEMENT_ARRAY_BUFFER,
                 utils::as_u8(indices),
                 Self::mem_hint(freq, nat),
             );
-            gl_result!(device.gl);
+            gl_result!(gl);
 
-            device.gl.bind_buffer(glow::ARRAY_BUFFER, None);
-            device.gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            gl.bind_vertex_array(None);
 
             Ok(Self {
                 vao: vertex_array,
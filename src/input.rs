@@ -1,3 +1,4 @@
+use crate::io::BuiltinModules;
 use rust_wren::{handle::WrenCallHandle, prelude::*, WrenContext, WrenResult};
 use winit::{
     dpi::{LogicalPosition, PhysicalPosition},
@@ -6,69 +7,87 @@ use winit::{
 
 pub const INPUT_MODULE: &str = "gers.input";
 
-pub fn register_input(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret(INPUT_MODULE, include_str!("input.wren"))
+pub fn register_input(builtins: &mut BuiltinModules) {
+    builtins.add(INPUT_MODULE, include_str!("input.wren"));
 }
 
-pub struct Mouse {
-    pub set_pos: WrenCallHandle,
-    pub push_button: WrenCallHandle,
+/// Input forwarded to scripts, in the form the Wren side receives it.
+///
+/// Kept independent of window events so it can be recorded
+/// and replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    MousePos { logical: [f64; 2], physical: [f64; 2] },
+    MouseButton { button: u8, pressed: bool },
+    KeyPress(String),
+    KeyRelease(String),
+    Char(char),
 }
 
-impl Mouse {
-    pub fn set_pos(
-        &mut self,
-        ctx: &mut WrenContext,
-        logical: LogicalPosition<f64>,
-        physical: PhysicalPosition<f64>,
-    ) -> WrenResult<()> {
-        self.set_pos
-            .call::<_, ()>(ctx, (logical.x, logical.y, physical.x, physical.y))
+impl InputEvent {
+    pub fn mouse_pos(logical: LogicalPosition<f64>, physical: PhysicalPosition<f64>) -> Self {
+        InputEvent::MousePos {
+            logical: [logical.x, logical.y],
+            physical: [physical.x, physical.y],
+        }
     }
 
-    pub fn push_button(&mut self, ctx: &mut WrenContext, button: MouseButton, state: ElementState) -> WrenResult<()> {
+    /// Returns `None` for buttons that scripts don't know about.
+    pub fn mouse_button(button: MouseButton, state: ElementState) -> Option<Self> {
         // TODO: Do one of these 3 overlap with possible `Other(_)` values?
-        let button_id = match button {
+        let button = match button {
             MouseButton::Left => 1,
             MouseButton::Middle => 2,
             MouseButton::Right => 3,
-            MouseButton::Other(_) => unimplemented!("Other buttons not implemented yet"),
+            MouseButton::Other(_) => return None,
         };
-        let state_bool = match state {
-            ElementState::Pressed => true,
-            ElementState::Released => false,
-        };
-        self.push_button.call::<_, ()>(ctx, (button_id, state_bool))
-    }
-}
 
-pub struct Keyboard {
-    pub set_key_press: WrenCallHandle,
-    pub set_key_release: WrenCallHandle,
-    pub push_char: WrenCallHandle,
-}
+        Some(InputEvent::MouseButton {
+            button,
+            pressed: state == ElementState::Pressed,
+        })
+    }
 
-impl Keyboard {
-    pub fn set_key_state(
-        &mut self,
-        ctx: &mut WrenContext,
-        keycode: VirtualKeyCode,
-        state: ElementState,
-    ) -> WrenResult<()> {
+    pub fn key(keycode: VirtualKeyCode, state: ElementState) -> Self {
+        let name = format!("{:?}", keycode);
         match state {
-            ElementState::Pressed => self.set_key_press.call::<_, ()>(ctx, format!("{:?}", keycode)),
-            ElementState::Released => self.set_key_release.call::<_, ()>(ctx, format!("{:?}", keycode)),
+            ElementState::Pressed => InputEvent::KeyPress(name),
+            ElementState::Released => InputEvent::KeyRelease(name),
         }
     }
 
-    pub fn push_char(&mut self, ctx: &mut WrenContext, input: char) -> WrenResult<()> {
-        // TODO:
-        //  Horribly inefficient. We're copying a string (ToWren) on each press,
-        //  and incurring an allocation in Wren.
-        //  It will be much better to send the char to Wren as a u32, but we
-        //  will have to encode it into a UTF-8 string in Wren.
-        let mut buf = [0; 4];
-        let s: &str = input.encode_utf8(&mut buf);
-        self.push_char.call::<_, ()>(ctx, s)
+    /// Calls into the input module with the event.
+    pub fn send(&self, ctx: &mut WrenContext, mouse: &Mouse, keyboard: &Keyboard) -> WrenResult<()> {
+        match self {
+            InputEvent::MousePos { logical, physical } => mouse
+                .set_pos
+                .call::<_, ()>(ctx, (logical[0], logical[1], physical[0], physical[1])),
+            InputEvent::MouseButton { button, pressed } => {
+                mouse.push_button.call::<_, ()>(ctx, (*button as f64, *pressed))
+            }
+            InputEvent::KeyPress(name) => keyboard.set_key_press.call::<_, ()>(ctx, name.as_str()),
+            InputEvent::KeyRelease(name) => keyboard.set_key_release.call::<_, ()>(ctx, name.as_str()),
+            InputEvent::Char(input) => {
+                // TODO:
+                //  Horribly inefficient. We're copying a string (ToWren) on each press,
+                //  and incurring an allocation in Wren.
+                //  It will be much better to send the char to Wren as a u32, but we
+                //  will have to encode it into a UTF-8 string in Wren.
+                let mut buf = [0; 4];
+                let s: &str = input.encode_utf8(&mut buf);
+                keyboard.push_char.call::<_, ()>(ctx, s)
+            }
+        }
     }
 }
+
+pub struct Mouse {
+    pub set_pos: WrenCallHandle,
+    pub push_button: WrenCallHandle,
+}
+
+pub struct Keyboard {
+    pub set_key_press: WrenCallHandle,
+    pub set_key_release: WrenCallHandle,
+    pub push_char: WrenCallHandle,
+}
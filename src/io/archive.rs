@@ -0,0 +1,402 @@
+//! Single file archive of scripts and assets, for shipping a game.
+//!
+//! The archive starts with a header and an index of its entries,
+//! followed by the entry data. Entries are named by their path
+//! relative to the module root or asset directory they were
+//! packed from, using `/` as separator.
+//!
+//! Each entry is optionally compressed with deflate, and carries a
+//! CRC-32 of its uncompressed data which is checked when it's read.
+//!
+//! All numbers are little endian.
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const MAGIC: &[u8; 4] = b"GPAK";
+const VERSION: u8 = 1;
+
+const FLAG_COMPRESSED: u8 = 1;
+
+/// Compression level given to deflate, between 0 and 10.
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// Size of the header, before the index.
+const HEADER_SIZE: u64 = 4 + 1 + 4;
+
+/// Smallest size of an entry in the index, which has an empty name.
+const MIN_INDEX_ENTRY_SIZE: u64 = 2 + 1 + 8 + 8 + 8 + 4;
+
+#[derive(Debug, Clone, PartialEq)]
+struct ArchiveEntry {
+    flags: u8,
+    /// Offset of the data from the end of the index.
+    offset: u64,
+    /// Size of the data as stored in the archive.
+    stored_size: u64,
+    /// Size of the uncompressed data.
+    size: u64,
+    crc: u32,
+}
+
+/// Archive opened for reading.
+pub struct Archive {
+    path: PathBuf,
+    entries: HashMap<String, ArchiveEntry>,
+    /// Position in the file where entry data starts.
+    data_start: u64,
+}
+
+impl Archive {
+    /// File name that the engine looks for when there's no project manifest.
+    pub const FILE_NAME: &'static str = "game.gpak";
+
+    /// Opens an archive, reading its index.
+    ///
+    /// Sizes in the index are checked against the file, so a
+    /// truncated or corrupt archive is rejected before anything
+    /// is allocated for it.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a game archive"));
+        }
+
+        let version = read_u8(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported archive version {}", version)));
+        }
+
+        let count = read_u32(&mut reader)?;
+        if count as u64 * MIN_INDEX_ENTRY_SIZE > file_len.saturating_sub(HEADER_SIZE) {
+            return Err(invalid_data("archive index is truncated"));
+        }
+        let mut entries = HashMap::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let name_len = read_u16(&mut reader)? as usize;
+            let mut name = vec![0; name_len];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| invalid_data("invalid entry name"))?;
+
+            let entry = ArchiveEntry {
+                flags: read_u8(&mut reader)?,
+                offset: read_u64(&mut reader)?,
+                stored_size: read_u64(&mut reader)?,
+                size: read_u64(&mut reader)?,
+                crc: read_u32(&mut reader)?,
+            };
+            entries.insert(name, entry);
+        }
+
+        let data_start = reader.stream_position()?;
+
+        let data_len = file_len.saturating_sub(data_start);
+        for (name, entry) in &entries {
+            let end = entry.offset.checked_add(entry.stored_size);
+            if end.map(|end| end > data_len).unwrap_or(true) {
+                return Err(invalid_data(format!("archive entry '{}' is truncated", name)));
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+            data_start,
+        })
+    }
+
+    /// Searches the directories, in order, for an archive with the default file name.
+    pub fn find<P: AsRef<Path>>(dirs: &[P]) -> Option<PathBuf> {
+        dirs.iter()
+            .map(|dir| dir.as_ref().join(Self::FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Reads the uncompressed data of an entry, checking its integrity.
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .get(name)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("'{}' is not in the archive", name)))?;
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.data_start + entry.offset))?;
+        let mut stored = vec![0; entry.stored_size as usize];
+        file.read_exact(&mut stored)?;
+
+        let data = if entry.flags & FLAG_COMPRESSED != 0 {
+            miniz_oxide::inflate::decompress_to_vec_with_limit(&stored, inflate_limit(entry))
+                .map_err(|err| invalid_data(format!("failed to decompress '{}': {:?}", name, err)))?
+        } else {
+            stored
+        };
+
+        if data.len() as u64 != entry.size || crc32fast::hash(&data) != entry.crc {
+            return Err(invalid_data(format!("archive entry '{}' is corrupt", name)));
+        }
+
+        Ok(data)
+    }
+
+    pub fn read_to_string(&self, name: &str) -> io::Result<String> {
+        String::from_utf8(self.read(name)?).map_err(|_| invalid_data(format!("'{}' is not valid UTF-8", name)))
+    }
+}
+
+/// Builds an archive in memory, then writes it out.
+#[derive(Default)]
+pub struct ArchiveWriter {
+    compress: bool,
+    entries: Vec<(String, ArchiveEntry, Vec<u8>)>,
+    size: u64,
+}
+
+impl ArchiveWriter {
+    pub fn new(compress: bool) -> Self {
+        Self {
+            compress,
+            ..Default::default()
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|(entry_name, ..)| entry_name == name)
+    }
+
+    /// Adds an entry. Data that doesn't get smaller is stored uncompressed.
+    pub fn add(&mut self, name: impl Into<String>, data: Vec<u8>) -> io::Result<()> {
+        let name = name.into();
+        if name.len() > u16::MAX as usize {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "entry name is too long"));
+        }
+        if self.contains(&name) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("'{}' is already in the archive", name),
+            ));
+        }
+
+        let size = data.len() as u64;
+        let crc = crc32fast::hash(&data);
+
+        let (flags, stored) = if self.compress {
+            let compressed = miniz_oxide::deflate::compress_to_vec(&data, COMPRESSION_LEVEL);
+            if compressed.len() < data.len() {
+                (FLAG_COMPRESSED, compressed)
+            } else {
+                (0, data)
+            }
+        } else {
+            (0, data)
+        };
+
+        let entry = ArchiveEntry {
+            flags,
+            offset: self.size,
+            stored_size: stored.len() as u64,
+            size,
+            crc,
+        };
+        self.size += entry.stored_size;
+        self.entries.push((name, entry, stored));
+
+        Ok(())
+    }
+
+    /// Adds a file under the given entry name.
+    pub fn add_file<P: AsRef<Path>>(&mut self, name: impl Into<String>, path: P) -> io::Result<()> {
+        self.add(name, fs::read(path)?)
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+
+        for (name, entry, _) in &self.entries {
+            writer.write_all(&(name.len() as u16).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+            writer.write_all(&[entry.flags])?;
+            writer.write_all(&entry.offset.to_le_bytes())?;
+            writer.write_all(&entry.stored_size.to_le_bytes())?;
+            writer.write_all(&entry.size.to_le_bytes())?;
+            writer.write_all(&entry.crc.to_le_bytes())?;
+        }
+
+        for (_, _, data) in &self.entries {
+            writer.write_all(data)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Converts a relative file path to an entry name.
+///
+/// Returns `None` for paths that leave their directory.
+pub fn entry_name<P: AsRef<Path>>(path: P) -> Option<String> {
+    let mut parts = vec![];
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    Some(parts.join("/"))
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R: Read>(r: &mut R) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Largest buffer an entry may inflate into.
+///
+/// The decompressor doubles its buffer as it goes and treats an exactly
+/// full buffer as having more output, so the limit leaves room for that
+/// over the declared size. Anything bigger than the declared size is
+/// still rejected by the size check after inflating.
+fn inflate_limit(entry: &ArchiveEntry) -> usize {
+    entry.size.max(entry.stored_size).saturating_mul(2) as usize
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_archive(name: &str, compress: bool) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut writer = ArchiveWriter::new(compress);
+        writer.add("main.wren", b"System.print(\"Hello\")".to_vec()).unwrap();
+        writer.add("gfx/big.txt", vec![b'a'; 4096]).unwrap();
+        writer.add("empty", vec![]).unwrap();
+        assert!(writer.add("empty", vec![]).is_err());
+        writer.write(File::create(&path).unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for compress in &[false, true] {
+            let path = write_archive(&format!("gers_test_archive_{}.gpak", compress), *compress);
+            let archive = Archive::open(&path).unwrap();
+
+            assert_eq!(archive.entries.len(), 3);
+            assert_eq!(archive.read_to_string("main.wren").unwrap(), "System.print(\"Hello\")");
+            assert_eq!(archive.read("gfx/big.txt").unwrap(), vec![b'a'; 4096]);
+            assert_eq!(archive.read("empty").unwrap(), Vec::<u8>::new());
+            assert_eq!(archive.read("missing").unwrap_err().kind(), ErrorKind::NotFound);
+
+            let stored_size = archive.entries["gfx/big.txt"].stored_size;
+            assert_eq!(stored_size < 4096, *compress);
+
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let path = write_archive("gers_test_archive_corrupt.gpak", false);
+
+        // Flip the last byte, which belongs to the big entry
+        // since the empty entry has no data.
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.read("gfx/big.txt").unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(archive.read("main.wren").is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_truncated() {
+        let path = write_archive("gers_test_archive_truncated.gpak", false);
+        let bytes = fs::read(&path).unwrap();
+
+        // Index claims more entries than the file could hold.
+        let mut header = bytes[..HEADER_SIZE as usize].to_vec();
+        header[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&path, header).unwrap();
+        assert_eq!(Archive::open(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+
+        // Entry data is cut off.
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(Archive::open(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_inflate_limit() {
+        let path = write_archive("gers_test_archive_inflate_limit.gpak", true);
+        let mut archive = Archive::open(&path).unwrap();
+
+        // Index understates the inflated size, so decompression
+        // stops at the limit instead of inflating the whole entry.
+        archive.entries.get_mut("gfx/big.txt").unwrap().size = 16;
+        assert!(inflate_limit(&archive.entries["gfx/big.txt"]) < 4096);
+        assert_eq!(archive.read("gfx/big.txt").unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(archive.read("main.wren").is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_entry_name() {
+        assert_eq!(entry_name("gfx/player.png").as_deref(), Some("gfx/player.png"));
+        assert_eq!(entry_name("./main.wren").as_deref(), Some("main.wren"));
+        assert_eq!(entry_name("../main.wren"), None);
+        assert_eq!(entry_name("/main.wren"), None);
+    }
+}
@@ -0,0 +1,80 @@
+//! Lookup of asset files loaded by scripts.
+use super::archive::{entry_name, Archive};
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+thread_local! {
+    /// Directories searched for assets, in order. The Wren VM
+    /// runs on the main thread, so it's the only one that sets them.
+    static ASSET_DIRS: RefCell<Vec<PathBuf>> = RefCell::new(vec![]);
+
+    /// Archive that assets are read from before the asset directories.
+    static ASSET_ARCHIVE: RefCell<Option<Rc<Archive>>> = RefCell::new(None);
+}
+
+/// Sets the directories that relative asset paths are searched in.
+pub fn set_asset_dirs(dirs: Vec<PathBuf>) {
+    ASSET_DIRS.with(|asset_dirs| *asset_dirs.borrow_mut() = dirs);
+}
+
+/// Sets the archive that relative asset paths are looked up in first.
+pub fn set_asset_archive(archive: Option<Rc<Archive>>) {
+    ASSET_ARCHIVE.with(|asset_archive| *asset_archive.borrow_mut() = archive);
+}
+
+/// Resolves an asset path against the asset directories.
+///
+/// Absolute paths, and paths that aren't found in any asset
+/// directory, are returned as given, so they're relative to
+/// the working directory.
+pub fn resolve_asset<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    ASSET_DIRS.with(|asset_dirs| {
+        asset_dirs
+            .borrow()
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.is_file())
+            .unwrap_or_else(|| path.to_path_buf())
+    })
+}
+
+/// Reads an asset from the archive, when the game was packed,
+/// or else from the file resolved by `resolve_asset`.
+pub fn read_asset<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let path = path.as_ref();
+
+    let archive = ASSET_ARCHIVE.with(|asset_archive| asset_archive.borrow().clone());
+    if let (Some(archive), Some(name)) = (archive, entry_name(path)) {
+        if archive.contains(&name) {
+            return archive.read(&name);
+        }
+    }
+
+    fs::read(resolve_asset(path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_asset() {
+        let dir = env!("CARGO_MANIFEST_DIR");
+        set_asset_dirs(vec![Path::new(dir).join("src"), Path::new(dir).join("examples")]);
+
+        assert_eq!(
+            resolve_asset("test_pattern.png"),
+            Path::new(dir).join("examples/test_pattern.png")
+        );
+        assert_eq!(resolve_asset("missing.png"), PathBuf::from("missing.png"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Builtin modules embedded in the executable.
+use super::loader::ModulePath;
+use smol_str::SmolStr;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Virtual directory of the builtin modules' scripts, which the
+/// module loader falls back to when a module isn't found in the
+/// roots. A project can override a builtin module by placing a
+/// script with the same path in one of its roots.
+///
+/// A module can be made of several scripts, which are joined in
+/// the order they were added.
+///
+/// A scoped module has a copy for each module that imports it, with
+/// its source generated from the importer's name. Imports of it are
+/// rewritten to `<module>.<importer>`.
+#[derive(Debug, Default, Clone)]
+pub struct BuiltinModules {
+    files: HashMap<PathBuf, Vec<Cow<'static, str>>>,
+    /// Generators of scoped modules, by the directory of their copies.
+    scoped: HashMap<PathBuf, (SmolStr, fn(&str) -> String)>,
+}
+
+impl BuiltinModules {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a script to the module.
+    ///
+    /// # Panics
+    ///
+    /// When the module name is invalid.
+    pub fn add<S>(&mut self, module: &str, source: S)
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        let mod_path = ModulePath::new(module).expect("Invalid builtin module name");
+        self.files
+            .entry(mod_path.as_path().to_path_buf())
+            .or_default()
+            .push(source.into());
+    }
+
+    /// Makes the module scoped, with the given function generating
+    /// the source of each importer's copy.
+    ///
+    /// # Panics
+    ///
+    /// When the module name is invalid.
+    pub fn add_scoped(&mut self, module: &str, generate: fn(importer: &str) -> String) {
+        let mod_path = ModulePath::new(module).expect("Invalid builtin module name");
+        self.scoped
+            .insert(mod_path.as_path().with_extension(""), (SmolStr::from(module), generate));
+    }
+
+    /// Name of the importer's copy of the module, when it's scoped.
+    pub fn scoped_name(&self, module: &str, importer: &str) -> Option<String> {
+        let is_scoped = self.scoped.values().any(|(name, _)| name == module);
+        // Copies import the module itself.
+        let is_copy = importer.starts_with(module) && importer[module.len()..].starts_with('.');
+
+        if is_scoped && !is_copy {
+            Some(format!("{}.{}", module, importer))
+        } else {
+            None
+        }
+    }
+
+    /// Whether there's a builtin module at the file path.
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.read(path).is_some()
+    }
+
+    /// Source of the builtin module at the file path.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> Option<String> {
+        let path = path.as_ref();
+        if let Some(sources) = self.files.get(path) {
+            return Some(sources.join("\n"));
+        }
+
+        self.scoped.iter().find_map(|(dir, (_, generate))| {
+            let importer = ModulePath::from_file_path(path.strip_prefix(dir).ok()?).ok()?;
+            Some(generate(&importer.to_string()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builtin_modules() {
+        let mut builtins = BuiltinModules::new();
+        builtins.add("gers.graphics", "class Color {}");
+        builtins.add("gers.graphics", format!("class {} {{}}", "Sprite"));
+
+        assert!(builtins.contains("gers/graphics.wren"));
+        assert!(!builtins.contains("gers/input.wren"));
+        assert_eq!(
+            builtins.read("gers/graphics.wren").as_deref(),
+            Some("class Color {}\nclass Sprite {}")
+        );
+    }
+
+    #[test]
+    fn test_scoped_modules() {
+        let mut builtins = BuiltinModules::new();
+        builtins.add("gers.log", "class Logger {}");
+        builtins.add_scoped("gers.log", |importer| format!("var Log = \"{}\"", importer));
+
+        assert_eq!(
+            builtins.scoped_name("gers.log", "game.player").as_deref(),
+            Some("gers.log.game.player")
+        );
+        assert_eq!(builtins.scoped_name("gers.log", "gers.log.game"), None);
+        assert_eq!(builtins.scoped_name("gers.graphics", "game.player"), None);
+
+        assert_eq!(builtins.read("gers/log.wren").as_deref(), Some("class Logger {}"));
+        assert_eq!(
+            builtins.read("gers/log/game/player.wren").as_deref(),
+            Some("var Log = \"game.player\"")
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! Scanning of the import statements in Wren source.
+//!
+//! Wren identifies modules by the name they're imported with, so a
+//! relative name like `"./util"` would refer to a different file
+//! depending on the importing module. Before a module is handed to
+//! the VM, its relative imports are rewritten to absolute names.
+use std::ops::Range;
+
+/// Module name imported by an `import` statement.
+#[derive(Debug, PartialEq)]
+pub struct Import {
+    pub name: String,
+    /// Byte range of the name in the source, without quotes.
+    pub span: Range<usize>,
+    /// Variables imported from the module, by their name in it.
+    pub variables: Vec<String>,
+}
+
+impl Import {
+    /// Line of the import statement, starting at 1.
+    pub fn line(&self, source: &str) -> usize {
+        source[..self.span.start].matches('\n').count() + 1
+    }
+}
+
+/// Names starting with `./` or `../` are relative to the importing module.
+pub fn is_relative(name: &str) -> bool {
+    name.starts_with("./") || name.starts_with("../")
+}
+
+/// Finds the names of all import statements, skipping comments and strings.
+///
+/// Names containing escapes or interpolation are skipped, since
+/// they can't be resolved without running the script.
+pub fn scan_imports(source: &str) -> Vec<Import> {
+    scan(source).imports
+}
+
+/// Finds the variables declared at the top level of a module, which
+/// other modules can import. These are classes, variables and the
+/// variables the module itself imported.
+pub fn scan_variables(source: &str) -> Vec<String> {
+    scan(source).variables
+}
+
+fn scan(source: &str) -> Scanner<'_> {
+    let mut scanner = Scanner {
+        bytes: source.as_bytes(),
+        pos: 0,
+        braces: 0,
+        imports: vec![],
+        variables: vec![],
+    };
+    scanner.scan_code(false);
+    scanner
+}
+
+/// Replaces the names of import statements. Names for which the
+/// function returns `None` are left as they are.
+pub fn rewrite_imports<F>(source: &str, imports: &[Import], mut f: F) -> String
+where
+    F: FnMut(&Import) -> Option<String>,
+{
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for import in imports {
+        if let Some(name) = f(import) {
+            result.push_str(&source[last..import.span.start]);
+            result.push_str(&name);
+            last = import.span.end;
+        }
+    }
+
+    result.push_str(&source[last..]);
+    result
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Depth of nested blocks, where 0 is the top level of the module.
+    braces: usize,
+    imports: Vec<Import>,
+    variables: Vec<String>,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, s: &[u8]) -> bool {
+        self.bytes[self.pos..].starts_with(s)
+    }
+
+    /// Scans code until the end of the source or, inside string
+    /// interpolation, the closing parenthesis.
+    fn scan_code(&mut self, interpolation: bool) {
+        let mut depth = 0;
+
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'/' if self.peek(1) == Some(b'/') => self.skip_line_comment(),
+                b'/' if self.peek(1) == Some(b'*') => self.skip_block_comment(),
+                b'"' => {
+                    self.skip_string();
+                }
+                b'(' => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                b')' => {
+                    self.pos += 1;
+                    if interpolation && depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                b'{' => {
+                    self.braces += 1;
+                    self.pos += 1;
+                }
+                b'}' => {
+                    self.braces = self.braces.saturating_sub(1);
+                    self.pos += 1;
+                }
+                c if is_identifier_start(c) => {
+                    let identifier = self.scan_identifier();
+                    match identifier {
+                        b"import" => self.scan_import(),
+                        b"class" | b"var" if self.braces == 0 => {
+                            self.skip_whitespace();
+                            if self.peek(0).map(is_identifier_start).unwrap_or(false) {
+                                let name = self.scan_identifier();
+                                self.variables.push(String::from_utf8_lossy(name).into_owned());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn scan_identifier(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek(0).map(is_identifier_char).unwrap_or(false) {
+            self.pos += 1;
+        }
+        &self.bytes[start..self.pos]
+    }
+
+    fn scan_import(&mut self) {
+        self.skip_whitespace();
+
+        if self.peek(0) != Some(b'"') || self.starts_with(b"\"\"\"") {
+            return;
+        }
+
+        let start = self.pos + 1;
+        if self.skip_string() {
+            let end = self.pos - 1;
+            // Source is valid UTF-8, and the quotes are ASCII.
+            let name = String::from_utf8_lossy(&self.bytes[start..end]).into_owned();
+            let variables = self.scan_import_variables();
+            self.imports.push(Import {
+                name,
+                span: start..end,
+                variables,
+            });
+        }
+    }
+
+    /// Scans the `for` clause of an import, which has to start on
+    /// the same line. Aliased variables are declared by their alias.
+    fn scan_import_variables(&mut self) -> Vec<String> {
+        let mut variables = vec![];
+
+        let start = self.pos;
+        while let Some(b' ') | Some(b'\t') = self.peek(0) {
+            self.pos += 1;
+        }
+        if !self.starts_with(b"for") || self.peek(3).map(is_identifier_char).unwrap_or(false) {
+            self.pos = start;
+            return variables;
+        }
+        self.pos += 3;
+
+        loop {
+            self.skip_whitespace();
+            if !self.peek(0).map(is_identifier_start).unwrap_or(false) {
+                break;
+            }
+            let name = String::from_utf8_lossy(self.scan_identifier()).into_owned();
+
+            let before_alias = self.pos;
+            self.skip_whitespace();
+            let declared = if self.starts_with(b"as") && !self.peek(2).map(is_identifier_char).unwrap_or(false) {
+                self.pos += 2;
+                self.skip_whitespace();
+                String::from_utf8_lossy(self.scan_identifier()).into_owned()
+            } else {
+                self.pos = before_alias;
+                name.clone()
+            };
+
+            variables.push(name);
+            if self.braces == 0 && !declared.is_empty() {
+                self.variables.push(declared);
+            }
+
+            let before_comma = self.pos;
+            self.skip_whitespace();
+            if self.peek(0) == Some(b',') {
+                self.pos += 1;
+            } else {
+                self.pos = before_comma;
+                break;
+            }
+        }
+
+        variables
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek(0) {
+                Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') => self.pos += 1,
+                Some(b'/') if self.peek(1) == Some(b'/') => self.skip_line_comment(),
+                Some(b'/') if self.peek(1) == Some(b'*') => self.skip_block_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_line_comment(&mut self) {
+        while let Some(c) = self.peek(0) {
+            if c == b'\n' {
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Block comments nest in Wren.
+    fn skip_block_comment(&mut self) {
+        let mut depth = 0;
+
+        while self.pos < self.bytes.len() {
+            if self.starts_with(b"/*") {
+                depth += 1;
+                self.pos += 2;
+            } else if self.starts_with(b"*/") {
+                depth -= 1;
+                self.pos += 2;
+                if depth == 0 {
+                    return;
+                }
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skips a string literal, returning true when it's a plain
+    /// string without escapes or interpolation.
+    fn skip_string(&mut self) -> bool {
+        if self.starts_with(b"\"\"\"") {
+            self.pos += 3;
+            while self.pos < self.bytes.len() && !self.starts_with(b"\"\"\"") {
+                self.pos += 1;
+            }
+            self.pos = (self.pos + 3).min(self.bytes.len());
+            return false;
+        }
+
+        self.pos += 1;
+        let mut plain = true;
+
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'"' => {
+                    self.pos += 1;
+                    return plain;
+                }
+                b'\\' => {
+                    plain = false;
+                    self.pos += 2;
+                }
+                b'%' if self.peek(1) == Some(b'(') => {
+                    plain = false;
+                    self.pos += 2;
+                    self.scan_code(true);
+                }
+                _ => self.pos += 1,
+            }
+        }
+
+        // Unterminated string, which the compiler will report.
+        false
+    }
+}
+
+fn is_identifier_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+fn is_identifier_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(source: &str) -> Vec<String> {
+        scan_imports(source).into_iter().map(|import| import.name).collect()
+    }
+
+    #[test]
+    fn test_scan_imports() {
+        let source = r#"
+import "gers.graphics" for Transform2D
+import "./util" for Util
+// import "commented"
+/* import "block /* nested */ commented" */
+var s = "import \"in string\" %(import_("x"))"
+var r = """import "raw" """
+import
+  "../shared/math" for Vec2
+"#;
+        assert_eq!(names(source), vec!["gers.graphics", "./util", "../shared/math"]);
+
+        let imports = scan_imports(source);
+        assert_eq!(&source[imports[1].span.clone()], "./util");
+    }
+
+    #[test]
+    fn test_scan_variables() {
+        let source = r#"
+import "gers.graphics" for Color,
+  Transform2D as Transform
+import "gers.input"
+for (i in 0...3) {
+  var local = i
+}
+foreign class Sprite {
+  construct new() {}
+  static count { var inner = 0 }
+}
+class Player is Sprite {}
+var Speed = 10
+"#;
+        assert_eq!(
+            scan_variables(source),
+            vec!["Color", "Transform", "Sprite", "Player", "Speed"]
+        );
+
+        let imports = scan_imports(source);
+        assert_eq!(imports[0].variables, vec!["Color", "Transform2D"]);
+        assert_eq!(imports[0].line(source), 2);
+        assert!(imports[1].variables.is_empty());
+        assert_eq!(imports[1].line(source), 4);
+    }
+
+    #[test]
+    fn test_rewrite_imports() {
+        let source = r#"import "./util" for A
+import "gers.math" for B"#;
+        let imports = scan_imports(source);
+        let rewritten = rewrite_imports(source, &imports, |import| {
+            if is_relative(&import.name) {
+                Some("game.util".to_string())
+            } else {
+                None
+            }
+        });
+        assert_eq!(
+            rewritten,
+            r#"import "game.util" for A
+import "gers.math" for B"#
+        );
+    }
+
+    #[test]
+    fn test_is_relative() {
+        assert!(is_relative("./util"));
+        assert!(is_relative("../shared/math"));
+        assert!(!is_relative("gers.math"));
+        assert!(!is_relative(".util"));
+    }
+}
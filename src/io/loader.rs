@@ -1,17 +1,37 @@
 //! Wren module loader.
+use super::{
+    archive::{entry_name, Archive},
+    builtins::BuiltinModules,
+    imports::{is_relative, rewrite_imports, scan_imports},
+    watch::ModuleSources,
+};
+use crate::profiler;
 use rust_wren::prelude::*;
 use smol_str::SmolStr;
 use std::{
+    collections::HashMap,
     error::Error,
     fmt, fs, iter,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
 };
 
+/// Environment variable with extra module roots, separated like `PATH`.
+pub const GERS_PATH: &str = "GERS_PATH";
+
 pub struct WrenModuleLoader {
     /// Loader internal state is infallible
     /// because one root must be defined.
     root: PathBuf,
     rest: Vec<PathBuf>,
+    /// Record of the files modules were loaded from.
+    sources: Option<ModuleSources>,
+    /// Packed game, searched before the roots.
+    archive: Option<Rc<Archive>>,
+    /// Embedded builtin modules, searched after the roots.
+    builtins: BuiltinModules,
+    /// Modules imported by each loaded module, to detect cycles.
+    imports: HashMap<SmolStr, Vec<SmolStr>>,
 }
 
 impl WrenModuleLoader {
@@ -22,6 +42,10 @@ impl WrenModuleLoader {
         Self {
             root: root.as_ref().to_path_buf(),
             rest: vec![],
+            sources: None,
+            archive: None,
+            builtins: BuiltinModules::new(),
+            imports: HashMap::new(),
         }
     }
 
@@ -40,14 +64,42 @@ impl WrenModuleLoader {
         self
     }
 
+    /// Record the files that modules are loaded from, so they
+    /// can be watched for changes.
+    pub fn with_sources(mut self, sources: ModuleSources) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    /// Load modules from a packed game. Modules in the archive
+    /// take precedence over files in the roots.
+    pub fn with_archive(mut self, archive: Option<Rc<Archive>>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Load builtin modules that aren't overridden by a file in the
+    /// archive or roots.
+    pub fn with_builtins(mut self, builtins: BuiltinModules) -> Self {
+        self.builtins = builtins;
+        self
+    }
+
     /// An iterator of root paths.
     pub fn iter_roots(&self) -> impl iter::Iterator<Item = &Path> {
         iter::once(self.root.as_path()).chain(self.rest.iter().map(|path_buf| path_buf.as_path()))
     }
 }
 
-impl ModuleLoader for WrenModuleLoader {
-    fn load(&mut self, name: &str) -> Option<String> {
+impl WrenModuleLoader {
+    /// Loads the module like the VM would, with its relative imports
+    /// resolved, and records where it was read from.
+    pub fn load_module(&mut self, name: &str) -> Option<LoadedModule> {
+        if is_relative(name) {
+            log::error!("Module load error: relative import '{}' could not be resolved", name);
+            return None;
+        }
+
         let mod_path = match ModulePath::new(name) {
             Ok(mod_path) => mod_path,
             Err(err) => {
@@ -57,15 +109,65 @@ impl ModuleLoader for WrenModuleLoader {
         };
         log::debug!("Loading module: {}", mod_path);
 
+        let (source, origin) = self.read_module(&mod_path)?;
+        let (source, imports) = resolve_imports(&source, &mod_path, &self.builtins);
+
+        let name = mod_path.mod_path.clone();
+        self.imports.insert(name.clone(), imports);
+        if let Some(cycle) = self.find_cycle(&name) {
+            log::warn!(
+                "Cyclic import: {}. Variables of a module are null until it has finished loading.",
+                cycle.join(" -> ")
+            );
+        }
+
+        Some(LoadedModule { name, source, origin })
+    }
+
+    /// Reads the source of the module from the archive, the first
+    /// root it's found in, or the builtin modules. On failure, every
+    /// path that was tried is logged.
+    fn read_module(&self, mod_path: &ModulePath) -> Option<(String, ModuleOrigin)> {
         let file_path = mod_path.as_path();
+        let mut tried = vec![];
 
-        for dir_path in self.iter_roots() {
+        if let (Some(archive), Some(name)) = (&self.archive, entry_name(file_path)) {
+            log::debug!("Attempting '{}' in archive", name);
+
+            if archive.contains(&name) {
+                return match archive.read_to_string(&name) {
+                    Ok(source) => Some((source, ModuleOrigin::Archive(name))),
+                    Err(err) => {
+                        log::error!("Module load error: {}", err);
+                        None
+                    }
+                };
+            }
+            tried.push(format!("{} in {}", name, archive.path().display()));
+        }
+
+        let roots: Vec<&Path> = self.iter_roots().collect();
+        for (index, dir_path) in roots.iter().enumerate() {
             let path = dir_path.join(file_path);
             log::debug!("Attempting '{}'", path.to_string_lossy());
 
             if path.is_file() {
-                return match fs::read_to_string(path) {
-                    Ok(source) => Some(source),
+                self.warn_shadowed(mod_path, &path, &roots[index + 1..]);
+                if self.builtins.contains(file_path) {
+                    log::info!(
+                        "Builtin module {} is overridden by '{}'",
+                        mod_path,
+                        path.to_string_lossy()
+                    );
+                }
+
+                return match fs::read_to_string(&path) {
+                    Ok(source) => {
+                        if let Some(sources) = &self.sources {
+                            sources.insert(path.clone());
+                        }
+                        Some((source, ModuleOrigin::File(path)))
+                    }
                     Err(err) => {
                         log::error!("Module load error: {}", err);
                         None
@@ -74,11 +176,151 @@ impl ModuleLoader for WrenModuleLoader {
             } else {
                 log::trace!("Module path is not a file: {}", path.to_string_lossy());
             }
+            tried.push(path.to_string_lossy().into_owned());
+        }
+
+        if let Some(source) = self.builtins.read(file_path) {
+            log::debug!("Loading builtin module {}", mod_path);
+            return Some((source, ModuleOrigin::Builtin));
+        }
+
+        log::error!("Module not found: {}, tried:\n  {}", mod_path, tried.join("\n  "));
+
+        // Builtin modules used to be imported without a namespace.
+        if let Ok(builtin) = ModulePath::new(format!("gers.{}", mod_path)) {
+            if self.builtins.contains(builtin.as_path()) {
+                log::error!("Builtin modules are in the gers namespace, did you mean {}?", builtin);
+            }
         }
 
-        log::warn!("Module not found: {}", mod_path);
         None
     }
+
+    /// Warns when the module file hides a file with the same
+    /// path in a later root.
+    fn warn_shadowed(&self, mod_path: &ModulePath, path: &Path, later_roots: &[&Path]) {
+        let canonical = fs::canonicalize(path).ok();
+
+        for root in later_roots {
+            let other = root.join(mod_path.as_path());
+            if other.is_file() && fs::canonicalize(&other).ok() != canonical {
+                log::warn!(
+                    "Module {} loaded from '{}' shadows '{}'",
+                    mod_path,
+                    path.to_string_lossy(),
+                    other.to_string_lossy()
+                );
+            }
+        }
+    }
+
+    /// Finds a chain of imports leading from the module back to itself.
+    fn find_cycle(&self, name: &SmolStr) -> Option<Vec<SmolStr>> {
+        let mut chain = vec![name.clone()];
+        if self.find_path(name, name, &mut chain) {
+            Some(chain)
+        } else {
+            None
+        }
+    }
+
+    fn find_path(&self, from: &SmolStr, to: &SmolStr, chain: &mut Vec<SmolStr>) -> bool {
+        for import in self.imports.get(from).into_iter().flatten() {
+            if import == to {
+                chain.push(import.clone());
+                return true;
+            }
+            if chain.contains(import) {
+                continue;
+            }
+
+            chain.push(import.clone());
+            if self.find_path(import, to, chain) {
+                return true;
+            }
+            chain.pop();
+        }
+
+        false
+    }
+}
+
+impl ModuleLoader for WrenModuleLoader {
+    fn load(&mut self, name: &str) -> Option<String> {
+        let _zone = profiler::zone("WrenModuleLoader::load").arg("module", name);
+        self.load_module(name).map(|module| module.source)
+    }
+}
+
+/// Source of a module found by the loader.
+#[derive(Debug)]
+pub struct LoadedModule {
+    pub name: SmolStr,
+    /// Source with relative imports resolved.
+    pub source: String,
+    pub origin: ModuleOrigin,
+}
+
+/// Where the source of a module was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleOrigin {
+    /// Entry in the packed game.
+    Archive(String),
+    File(PathBuf),
+    /// Script embedded in the executable.
+    Builtin,
+}
+
+impl fmt::Display for ModuleOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModuleOrigin::Archive(name) => write!(f, "{}", name),
+            ModuleOrigin::File(path) => write!(f, "{}", path.display()),
+            ModuleOrigin::Builtin => write!(f, "<builtin>"),
+        }
+    }
+}
+
+/// Rewrites the relative imports in the source of the module to
+/// absolute module names, which Wren can identify the modules by.
+/// Imports of scoped builtin modules are rewritten to the module's
+/// own copy.
+///
+/// Returns the rewritten source and the names of all imported modules.
+/// Imports that can't be resolved are logged and left as they are.
+pub fn resolve_imports(source: &str, module: &ModulePath, builtins: &BuiltinModules) -> (String, Vec<SmolStr>) {
+    let imports = scan_imports(source);
+    let mut names = vec![];
+
+    let source = rewrite_imports(source, &imports, |import| {
+        if !is_relative(&import.name) {
+            let scoped = builtins.scoped_name(&import.name, &module.mod_path);
+            names.push(SmolStr::from(scoped.as_deref().unwrap_or(&import.name)));
+            return scoped;
+        }
+
+        match module.resolve(&import.name) {
+            Ok(resolved) => {
+                log::trace!("Resolved import '{}' in {} to {}", import.name, module, resolved);
+                names.push(resolved.mod_path.clone());
+                Some(resolved.mod_path.to_string())
+            }
+            Err(err) => {
+                log::error!("Invalid import '{}' in {}: {}", import.name, module, err);
+                None
+            }
+        }
+    });
+
+    (source, names)
+}
+
+/// Module name of a script file, based on the first root that contains it.
+pub fn module_for_file<P: AsRef<Path>>(roots: &[P], path: &Path) -> Option<ModulePath> {
+    roots
+        .iter()
+        .filter_map(|root| path.strip_prefix(root).ok())
+        .find_map(|relative| ModulePath::from_file_path(relative).ok())
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -137,7 +379,7 @@ impl ModulePath {
 
         // Last path part, which is also considered the filename.
         if ident_buf.is_empty() {
-            let pos = module_name.as_ref().len() - 1;
+            let pos = module_name.as_ref().len().saturating_sub(1);
             return Err(ModuleNameError {
                 kind: E::UnexpectedEOS,
                 pos,
@@ -154,11 +396,73 @@ impl ModulePath {
         })
     }
 
+    /// Resolves a relative import, like `./util` or `../shared/math`,
+    /// against the directory of this module.
+    pub fn resolve(&self, relative: &str) -> Result<Self, ModuleNameError> {
+        use ModuleNameParseKind as E;
+
+        let mut parts: Vec<&str> = self.mod_path.split(Self::DELIMITER).collect();
+        // The module's own name.
+        parts.pop();
+
+        let mut pos = 0;
+        for segment in relative.split('/') {
+            match segment {
+                "." => {}
+                ".." => {
+                    if parts.pop().is_none() {
+                        return Err(ModuleNameError {
+                            kind: E::OutsideRoot,
+                            pos,
+                            snippet: Self::make_snippet(relative, pos),
+                        });
+                    }
+                }
+                _ => {
+                    let segment = segment.trim_end_matches(Self::WREN_EXT);
+                    if let Some(offset) = segment.find(Self::DELIMITER) {
+                        let pos = pos + offset;
+                        return Err(ModuleNameError {
+                            kind: E::InvalidCharacter,
+                            pos,
+                            snippet: Self::make_snippet(relative, pos),
+                        });
+                    }
+                    parts.push(segment);
+                }
+            }
+            pos += segment.len() + 1;
+        }
+
+        Self::new(parts.join(&Self::DELIMITER.to_string()))
+    }
+
+    /// Module for a script file, with a path relative to the module root.
+    pub fn from_file_path(path: &Path) -> Result<Self, ModuleNameError> {
+        let stem = path.with_extension("");
+        let mut parts = vec![];
+        for component in stem.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy()),
+                _ => {
+                    let name = path.to_string_lossy();
+                    return Err(ModuleNameError {
+                        kind: ModuleNameParseKind::OutsideRoot,
+                        pos: 0,
+                        snippet: Self::make_snippet(&name, 0),
+                    });
+                }
+            }
+        }
+
+        Self::new(parts.join(&Self::DELIMITER.to_string()))
+    }
+
     fn make_snippet(source: &str, pos: usize) -> String {
         if pos == 0 {
             // Start of string.
             format!("-->{}", source)
-        } else if pos == source.len() - 1 {
+        } else if pos + 1 >= source.len() {
             // End of string.
             format!("{}<--", source)
         } else {
@@ -210,6 +514,7 @@ impl fmt::Display for ModuleNameError {
             ),
             E::Whitespace => write!(f, "whitespace not allowed in module path '{}'", self.snippet),
             E::InvalidCharacter => write!(f, "invalid character at '{}'", self.snippet),
+            E::OutsideRoot => write!(f, "module path leads outside of the module root at '{}'", self.snippet),
         }
     }
 }
@@ -226,6 +531,8 @@ pub enum ModuleNameParseKind {
     Whitespace,
     /// Unexpected character.
     InvalidCharacter,
+    /// Relative path leads outside of the module root.
+    OutsideRoot,
 }
 
 #[cfg(test)]
@@ -266,4 +573,88 @@ mod test {
             })
         );
     }
+
+    #[test]
+    fn test_module_path_resolve() {
+        let module = ModulePath::new("game.scenes.title").unwrap();
+        let resolve = |relative| module.resolve(relative).map(|path| path.to_string());
+
+        assert_eq!(resolve("./menu"), Ok("game.scenes.menu".to_string()));
+        assert_eq!(resolve("../shared/math"), Ok("game.shared.math".to_string()));
+        assert_eq!(resolve("./ui/../menu.wren"), Ok("game.scenes.menu".to_string()));
+        assert_eq!(
+            resolve("../../../outside").map_err(|err| err.kind),
+            Err(ModuleNameParseKind::OutsideRoot)
+        );
+        assert_eq!(
+            resolve("./menu.old").map_err(|err| err.kind),
+            Err(ModuleNameParseKind::InvalidCharacter)
+        );
+
+        let path = ModulePath::from_file_path(Path::new("game/main.wren")).unwrap();
+        assert_eq!(path.to_string(), "game.main");
+        assert!(ModulePath::from_file_path(Path::new("../main.wren")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_imports() {
+        let module = ModulePath::new("game.main").unwrap();
+        let source = r#"import "./player" for Player
+import "gers.math" for Vec2
+import "gers.log" for Log
+import "../../escape" for Nothing"#;
+
+        let mut builtins = BuiltinModules::new();
+        builtins.add_scoped("gers.log", |importer| importer.to_string());
+
+        let (source, imports) = resolve_imports(source, &module, &builtins);
+        assert_eq!(
+            source,
+            r#"import "game.player" for Player
+import "gers.math" for Vec2
+import "gers.log.game.main" for Log
+import "../../escape" for Nothing"#
+        );
+        assert_eq!(
+            imports,
+            vec![
+                SmolStr::from("game.player"),
+                SmolStr::from("gers.math"),
+                SmolStr::from("gers.log.game.main")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let mut loader = WrenModuleLoader::from_root(".");
+        loader.imports.insert("a".into(), vec!["b".into(), "gers.math".into()]);
+        loader.imports.insert("b".into(), vec!["c".into()]);
+        assert_eq!(loader.find_cycle(&"a".into()), None);
+
+        loader.imports.insert("c".into(), vec!["a".into()]);
+        assert_eq!(
+            loader.find_cycle(&"b".into()),
+            Some(vec!["b".into(), "c".into(), "a".into(), "b".into()])
+        );
+    }
+
+    #[test]
+    fn test_builtin_override() {
+        let dir = std::env::temp_dir().join("gers_test_builtin_override");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("gers")).unwrap();
+        fs::write(dir.join("gers").join("input.wren"), "class Patched {}").unwrap();
+
+        let mut builtins = BuiltinModules::new();
+        builtins.add("gers.input", "class Keyboard {}");
+        builtins.add("gers.time", "class Timer {}");
+        let mut loader = WrenModuleLoader::from_root(&dir).with_builtins(builtins);
+
+        assert_eq!(loader.load("gers.input").as_deref(), Some("class Patched {}"));
+        assert_eq!(loader.load("gers.time").as_deref(), Some("class Timer {}"));
+        assert_eq!(loader.load("time"), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
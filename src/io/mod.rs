@@ -1,3 +1,13 @@
+mod archive;
+mod assets;
+mod builtins;
+mod imports;
 mod loader;
+mod watch;
 
-pub use self::loader::WrenModuleLoader;
+pub use self::archive::{entry_name, Archive, ArchiveWriter};
+pub use self::assets::{read_asset, set_asset_archive, set_asset_dirs};
+pub use self::builtins::BuiltinModules;
+pub use self::imports::{scan_imports, scan_variables};
+pub use self::loader::{module_for_file, resolve_imports, LoadedModule, ModuleOrigin, WrenModuleLoader, GERS_PATH};
+pub use self::watch::{FileWatcher, ModuleSources};
@@ -0,0 +1,145 @@
+//! Script file watching for hot reloading.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Shared record of the files that modules were loaded from.
+///
+/// The module loader is moved into the Wren VM, so the
+/// engine keeps a handle to the same list.
+#[derive(Debug, Default, Clone)]
+pub struct ModuleSources(Arc<Mutex<Vec<PathBuf>>>);
+
+impl ModuleSources {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn insert(&self, path: PathBuf) {
+        let mut paths = self.0.lock().unwrap();
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    /// Copy of the recorded file paths.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// Polls the modification time of script files.
+///
+/// Polling is throttled to an interval, so it's cheap
+/// enough to call every frame.
+pub struct FileWatcher {
+    sources: ModuleSources,
+    files: Vec<WatchedFile>,
+    interval: Duration,
+    last_poll: Instant,
+}
+
+struct WatchedFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Creates a watcher for the files recorded by the module loader.
+    pub fn new(sources: ModuleSources) -> Self {
+        Self {
+            sources,
+            files: vec![],
+            interval: Self::DEFAULT_INTERVAL,
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Minimum time between polls.
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Watch a file that was not loaded via the module loader,
+    /// like the entry point script.
+    pub fn watch<P>(&mut self, path: P)
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        if !self.files.iter().any(|file| file.path == path) {
+            self.files.push(WatchedFile {
+                path: path.to_path_buf(),
+                modified: Self::modified(path),
+            });
+        }
+    }
+
+    /// Returns the files that changed since the last poll.
+    ///
+    /// Returns nothing when called again before the interval has passed.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < self.interval {
+            return vec![];
+        }
+        self.last_poll = Instant::now();
+
+        // Modules can be imported after the watcher was created.
+        for path in self.sources.paths() {
+            self.watch(path);
+        }
+
+        let mut changed = vec![];
+
+        for file in self.files.iter_mut() {
+            let modified = Self::modified(&file.path);
+            if modified != file.modified {
+                file.modified = modified;
+                changed.push(file.path.clone());
+            }
+        }
+
+        changed
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn test_poll_changed() {
+        let dir = std::env::temp_dir().join("gers_test_poll_changed");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("module.wren");
+        fs::write(&path, "class Foo {}").unwrap();
+
+        let sources = ModuleSources::new();
+        let mut watcher = FileWatcher::new(sources.clone()).with_interval(Duration::from_secs(0));
+        sources.insert(path.clone());
+        assert!(watcher.poll().is_empty());
+
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use crate::manifest::LogLevel;
+use std::collections::BTreeMap;
+
+/// Most verbose level logged for each module.
+///
+/// A level given for a module applies to the modules nested in
+/// it too, so `game` covers `game.player`, unless a level is
+/// given for the nested module as well.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    level: LogLevel,
+    modules: BTreeMap<String, LogLevel>,
+}
+
+impl LogFilter {
+    /// Creates a filter with a default level for modules that
+    /// aren't in the map.
+    pub fn new(level: LogLevel, modules: BTreeMap<String, LogLevel>) -> Self {
+        Self { level, modules }
+    }
+
+    pub fn level_for(&self, module: &str) -> LogLevel {
+        let mut name = module;
+        loop {
+            if let Some(level) = self.modules.get(name) {
+                return *level;
+            }
+            match name.rfind('.') {
+                Some(pos) => name = &name[..pos],
+                None => return self.level,
+            }
+        }
+    }
+
+    pub fn is_enabled(&self, module: &str, level: LogLevel) -> bool {
+        level <= self.level_for(module)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_log_filter() {
+        let mut modules = BTreeMap::new();
+        modules.insert("game".to_string(), LogLevel::Debug);
+        modules.insert("game.physics".to_string(), LogLevel::Error);
+        let filter = LogFilter::new(LogLevel::Warn, modules);
+
+        assert_eq!(filter.level_for("game"), LogLevel::Debug);
+        assert_eq!(filter.level_for("game.player"), LogLevel::Debug);
+        assert_eq!(filter.level_for("game.physics.collision"), LogLevel::Error);
+        assert_eq!(filter.level_for("gameplay"), LogLevel::Warn);
+        assert_eq!(filter.level_for("gers.tween"), LogLevel::Warn);
+
+        assert!(filter.is_enabled("game.player", LogLevel::Info));
+        assert!(!filter.is_enabled("game.physics", LogLevel::Warn));
+        assert!(!filter.is_enabled("menu", LogLevel::Info));
+    }
+}
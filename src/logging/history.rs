@@ -0,0 +1,89 @@
+use slog::{Drain, Key, OwnedKVList, Record, Serializer, KV};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+/// Drain that keeps the most recent log lines in memory, so
+/// they can be included in crash reports.
+#[derive(Clone)]
+pub struct LogHistory {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl LogHistory {
+    pub const DEFAULT_CAPACITY: usize = 200;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Copies the kept lines, oldest first.
+    ///
+    /// Returns `None` when the lines are locked, which happens when
+    /// the current thread panicked while logging.
+    pub fn lines(&self) -> Option<Vec<String>> {
+        let lines = self.lines.try_lock().ok()?;
+        Some(lines.iter().cloned().collect())
+    }
+}
+
+impl Drain for LogHistory {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let mut line = format!("{} {}", record.level().as_short_str(), record.msg());
+        let mut serializer = LineSerializer(&mut line);
+        let _ = record.kv().serialize(record, &mut serializer);
+        let _ = values.serialize(record, &mut serializer);
+
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+
+        Ok(())
+    }
+}
+
+/// Appends key-value pairs to a log line.
+struct LineSerializer<'a>(&'a mut String);
+
+impl<'a> Serializer for LineSerializer<'a> {
+    fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+        let _ = write!(self.0, ", {}: {}", key, val);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use slog::{o, Logger};
+
+    #[test]
+    fn test_log_history() {
+        let history = LogHistory::new(2);
+        let logger = Logger::root(history.clone(), o!("lang" => "Rust"));
+
+        slog::info!(logger, "first");
+        slog::warn!(logger, "second"; "module" => "game");
+        slog::error!(logger, "third {}", 3);
+
+        assert_eq!(
+            history.lines().unwrap(),
+            vec![
+                "WARN second, module: game, lang: Rust".to_string(),
+                "ERRO third 3, lang: Rust".to_string(),
+            ]
+        );
+    }
+}
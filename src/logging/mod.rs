@@ -0,0 +1,87 @@
+//! Script logging, with messages tagged by the module that logged them.
+mod filter;
+mod history;
+
+pub const LOG_MODULE: &str = "gers.log";
+pub use self::filter::LogFilter;
+pub use self::history::LogHistory;
+
+use crate::{io::BuiltinModules, manifest::LogLevel};
+use rust_wren::{prelude::*, ModuleBuilder};
+use smol_str::SmolStr;
+use std::cell::RefCell;
+
+/// Tag of the records logged by scripts. They're filtered by
+/// module before they reach the drain, which lets them through
+/// regardless of the global level.
+pub const SCRIPT_TAG: &str = "script";
+
+thread_local! {
+    /// Logger and filter for script messages. The Wren VM runs on
+    /// the main thread, so it's the only one that sets them.
+    static SCRIPT_LOGGER: RefCell<Option<(slog::Logger, LogFilter)>> = RefCell::new(None);
+}
+
+/// Sets the logger that script messages are written to, and the
+/// levels they're filtered by.
+pub fn set_script_logger(logger: slog::Logger, filter: LogFilter) {
+    SCRIPT_LOGGER.with(|script_logger| *script_logger.borrow_mut() = Some((logger, filter)));
+}
+
+pub fn register_log(builtins: &mut BuiltinModules) {
+    builtins.add(LOG_MODULE, include_str!("log.wren"));
+    builtins.add_scoped(LOG_MODULE, module_log);
+}
+
+pub fn bind_log(module: &mut ModuleBuilder) {
+    module.register::<WrenLogger>();
+}
+
+/// Source of the importing module's copy of the log module, which
+/// declares `Log` as a logger tagged with the importer's name.
+fn module_log(importer: &str) -> String {
+    format!(
+        "import \"{}\" for Logger\nvar Log = Logger.new(\"{}\")\n",
+        LOG_MODULE, importer
+    )
+}
+
+#[wren_class(name = Logger)]
+pub struct WrenLogger {
+    module: SmolStr,
+}
+
+#[wren_methods]
+impl WrenLogger {
+    #[construct]
+    fn new(module: &str) -> Self {
+        Self {
+            module: SmolStr::from(module),
+        }
+    }
+
+    #[method(name = module_)]
+    fn module(&self) -> String {
+        self.module.to_string()
+    }
+
+    #[method(name = log_)]
+    fn log(&self, level: f64, message: &str) {
+        let level = match level as u8 {
+            0 => LogLevel::Error,
+            1 => LogLevel::Warn,
+            2 => LogLevel::Info,
+            3 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        };
+
+        SCRIPT_LOGGER.with(|script_logger| {
+            if let Some((logger, filter)) = &*script_logger.borrow() {
+                if filter.is_enabled(&self.module, level) {
+                    let module = self.module.as_str();
+                    slog::log!(logger, level.slog_level(), SCRIPT_TAG, "{}", message; "module" => module);
+                }
+            }
+        });
+    }
+}
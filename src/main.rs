@@ -5,18 +5,40 @@ extern crate slog;
 extern crate slog_async;
 extern crate slog_term;
 
+use self::check::{check_imports, collect_modules, compile_modules};
+use self::cli::{Command, RunArgs, TestArgs};
 use self::collections::{bind_collections, register_collections, COLLECTIONS_MODULE};
-use self::errors::{log_wren_error, GersError};
-use self::game::{init_game, register_game};
+use self::crash::{install_crash_reports, set_opengl_info, CRASH_DIR};
+use self::ecs::{bind_ecs, register_ecs, ECS_MODULE};
+use self::errors::{log_wren_error, GersResult};
+use self::game::{
+    bind_game, init_game, register_game, Game, HeadlessConfig, ReplayPlayer, ReplayRecorder, RunExit, GAME_MODULE,
+    MAIN_MODULE,
+};
 use self::graphics::{
     bind_graphic_device, bind_graphics, init_default_shaders, init_graphic_device, register_graphic_device,
     register_graphics, GraphicDevice, GRAPHICS_MODULE,
 };
 use self::input::register_input;
+use self::io::{
+    entry_name, module_for_file, resolve_imports, set_asset_archive, set_asset_dirs, Archive, BuiltinModules,
+    FileWatcher, LoadedModule, ModuleOrigin, ModuleSources, WrenModuleLoader, GERS_PATH,
+};
+use self::logging::{bind_log, register_log, set_script_logger, LogFilter, LogHistory, LOG_MODULE, SCRIPT_TAG};
+use self::manifest::{LogFormat, LogLevel, Manifest};
 use self::math::{bind_math, register_math, MATH_MODULE};
 use self::noise::{bind_noise, register_noise, NOISE_MODULE};
-use self::window::{bind_window, register_window, WrenWindowConfig, WINDOW_MODULE};
-use glutin::{dpi::LogicalSize, window::WindowBuilder, Api, ContextBuilder, GlProfile, GlRequest};
+use self::pack::pack_project;
+use self::profiler::{bind_profiler, enable_profiler, finish_profiler, register_profiler, Profiler, PROFILER_MODULE};
+use self::testing::{find_tests, register_test, run_test_file, TestSummary, TEST_MODULE};
+use self::time::register_time;
+use self::tween::{bind_tween, register_tween, TWEEN_MODULE};
+use self::window::{bind_window, init_window_control, register_window, WrenWindowConfig, WINDOW_MODULE};
+use glutin::{
+    dpi::{LogicalSize, PhysicalSize},
+    window::WindowBuilder,
+    Api, ContextBuilder, GlProfile, GlRequest, PossiblyCurrent, WindowedContext,
+};
 use rust_wren::{
     handle::{FnSymbolRef, WrenCallRef},
     prelude::*,
@@ -26,33 +48,260 @@ use slog::Drain;
 use std::{
     env, fs,
     path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
+mod check;
+mod cli;
+mod ecs;
 mod errors;
 mod game;
 #[macro_use]
 mod graphics;
 mod collections;
+mod crash;
 mod input;
 mod io;
+mod logging;
+mod manifest;
 mod marker;
 mod math;
 mod noise;
+mod pack;
+mod profiler;
+mod repl;
+mod testing;
+mod time;
+mod tween;
 mod util;
 mod window;
 
-/// Register the builtin modules in the given Wren VM by interpreting
-/// the script contents.
+/// Embedded scripts of the builtin modules, which are loaded
+/// when a script first imports them.
+fn builtin_modules() -> BuiltinModules {
+    let mut builtins = BuiltinModules::new();
+    register_math(&mut builtins);
+    register_collections(&mut builtins);
+    register_noise(&mut builtins);
+    register_window(&mut builtins);
+    register_input(&mut builtins);
+    register_time(&mut builtins);
+    register_graphics(&mut builtins);
+    register_graphic_device(&mut builtins);
+    register_tween(&mut builtins);
+    register_ecs(&mut builtins);
+    register_game(&mut builtins);
+    register_test(&mut builtins);
+    register_log(&mut builtins);
+    register_profiler(&mut builtins);
+    builtins.add(MAIN_MODULE, include_str!("main.wren"));
+    builtins
+}
+
+/// Module that the engine imports from. Its name can't be
+/// imported by scripts, so it doesn't hide one of theirs.
+const ENGINE_MODULE: &str = "<engine>";
+
+/// Load the bootstrap module, which imports the builtin modules
+/// that the engine calls into.
+///
+/// It's loaded like any other builtin module, so a project can
+/// override it with a `gers/main.wren` in one of its roots.
 fn load_builtins(vm: &mut WrenVm) -> WrenResult<()> {
-    register_math(vm)?;
-    register_collections(vm)?;
-    register_noise(vm)?;
-    register_window(vm)?;
-    register_input(vm)?;
-    register_graphics(vm)?;
-    register_graphic_device(vm)?;
-    vm.interpret("main", include_str!("main.wren"))?;
-    register_game(vm)?;
+    preload_module(vm, MAIN_MODULE)
+}
+
+/// Load a builtin module before any script imports it.
+fn preload_module(vm: &mut WrenVm, module: &str) -> WrenResult<()> {
+    vm.interpret(ENGINE_MODULE, &format!("import \"{}\"", module))
+}
+
+/// Destination of script output written with `System.print`.
+enum ScriptOutput {
+    /// Log each message.
+    Log(slog::Logger),
+    /// Write directly to standard output, for interactive use.
+    Stdout,
+}
+
+/// Create a Wren VM with the engine's foreign bindings.
+fn build_vm(loader: WrenModuleLoader, output: ScriptOutput) -> WrenVm {
+    WrenBuilder::new()
+        .with_module_loader(loader)
+        .with_module(MATH_MODULE, bind_math)
+        .with_module(GAME_MODULE, bind_game)
+        .with_module(WINDOW_MODULE, bind_window)
+        .with_module(GRAPHICS_MODULE, |module| {
+            bind_graphic_device(module);
+            bind_graphics(module);
+        })
+        .with_module(COLLECTIONS_MODULE, |module| {
+            bind_collections(module);
+        })
+        .with_module(NOISE_MODULE, bind_noise)
+        .with_module(TWEEN_MODULE, bind_tween)
+        .with_module(ECS_MODULE, bind_ecs)
+        .with_module(LOG_MODULE, bind_log)
+        .with_module(PROFILER_MODULE, bind_profiler)
+        .with_write_fn(move |msg| match &output {
+            ScriptOutput::Log(wren_logger) => {
+                if msg != "\n" {
+                    info!(wren_logger, "{}", msg)
+                }
+            }
+            ScriptOutput::Stdout => print!("{}", msg),
+        })
+        .build()
+}
+
+/// Directories that modules are searched in, in order: the executable
+/// directory, the working directory, the extra roots from the project
+/// manifest, then the roots in the `GERS_PATH` environment variable.
+///
+/// An entry point outside of these gets its own directory added as a root,
+/// so it can import the modules next to it.
+fn module_roots(app_root: &Path, manifest: &Manifest) -> std::io::Result<Vec<PathBuf>> {
+    let cwd = env::current_dir()?;
+
+    let mut candidates = vec![app_root.to_path_buf(), cwd.clone()];
+    candidates.extend(manifest.roots.iter().map(|root| cwd.join(root)));
+    if let Some(search_path) = env::var_os(GERS_PATH) {
+        candidates.extend(env::split_paths(&search_path).map(|root| cwd.join(root)));
+    }
+
+    // The same directory may be given more than once, which would
+    // look like every module in it shadows itself.
+    let mut roots: Vec<PathBuf> = vec![];
+    for root in candidates {
+        let root = fs::canonicalize(&root).unwrap_or(root);
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+
+    if let Some(entry_dir) = manifest
+        .entry
+        .as_ref()
+        .and_then(|entry| fs::canonicalize(entry).ok())
+        .and_then(|entry| entry.parent().map(Path::to_path_buf))
+    {
+        if !roots.iter().any(|root| entry_dir.starts_with(root)) {
+            roots.push(entry_dir);
+        }
+    }
+
+    Ok(roots)
+}
+
+/// Module loader that searches the packed game, if any, then the module
+/// roots, then the builtin modules.
+fn module_loader(roots: &[PathBuf], archive: Option<&Rc<Archive>>, sources: ModuleSources) -> WrenModuleLoader {
+    let mut loader = WrenModuleLoader::from_root(&roots[0]);
+    for root in &roots[1..] {
+        loader.add_root(root);
+    }
+
+    loader
+        .with_archive(archive.cloned())
+        .with_builtins(builtin_modules())
+        .with_sources(sources)
+}
+
+/// Reads the project manifest in the working directory. Without one, the
+/// game packed next to the executable, or in the working directory, is
+/// opened along with the manifest it was packed with.
+fn load_project(app_root: &Path, cwd: &Path) -> GersResult<(Manifest, Option<Rc<Archive>>)> {
+    if let Some(manifest) = Manifest::find(cwd)? {
+        return Ok((manifest, None));
+    }
+
+    let archive = match Archive::find(&[app_root, cwd]) {
+        Some(path) => Archive::open(path)?,
+        None => return Ok((Manifest::default(), None)),
+    };
+    let manifest = if archive.contains(Manifest::FILE_NAME) {
+        Manifest::parse(&archive.read_to_string(Manifest::FILE_NAME)?)?
+    } else {
+        Manifest::default()
+    };
+
+    Ok((manifest, Some(Rc::new(archive))))
+}
+
+/// Name of the entry point in the archive, when the game is packed.
+fn archived_entry(archive: Option<&Rc<Archive>>, entry_path: &Path) -> Option<String> {
+    let archive = archive?;
+    entry_name(entry_path).filter(|name| archive.contains(name))
+}
+
+/// Builds the root logger, which writes to standard output or appends
+/// to a file.
+///
+/// Records more verbose than the max level are dropped, except for the
+/// ones logged by scripts, which were already filtered by module. The
+/// records that pass are also kept in the history for crash reports.
+fn build_root_logger(
+    format: LogFormat,
+    file: Option<&Path>,
+    max_level: Arc<AtomicUsize>,
+    history: LogHistory,
+) -> std::io::Result<slog::Logger> {
+    let file = match file {
+        Some(path) => Some(fs::OpenOptions::new().create(true).append(true).open(path)?),
+        None => None,
+    };
+    let drain: Box<dyn Drain<Ok = (), Err = slog::Never> + Send> = match (format, file) {
+        (LogFormat::Term, None) => {
+            let decorator = slog_term::TermDecorator::new().build();
+            Box::new(slog_term::FullFormat::new(decorator).build().fuse())
+        }
+        (LogFormat::Term, Some(file)) => {
+            let decorator = slog_term::PlainDecorator::new(file);
+            Box::new(slog_term::FullFormat::new(decorator).build().fuse())
+        }
+        (LogFormat::Json, None) => Box::new(slog_json::Json::default(std::io::stdout()).fuse()),
+        (LogFormat::Json, Some(file)) => Box::new(slog_json::Json::default(file).fuse()),
+    };
+    let drain = slog_async::Async::new(drain).build().fuse();
+    // History is kept before the asynchronous drain, so it's up to date when a report is written.
+    let drain = slog::Duplicate::new(drain, history)
+        .ignore_res()
+        .filter(move |record: &slog::Record| {
+            record.tag() == SCRIPT_TAG || record.level().as_usize() <= max_level.load(Ordering::Relaxed)
+        })
+        .ignore_res();
+    Ok(slog::Logger::root(drain, o!()))
+}
+
+/// Sets the most verbose level that's logged, by both `slog` and `log`.
+fn set_log_level(max_level: &AtomicUsize, level: LogLevel) {
+    max_level.store(level.slog_level().as_usize(), Ordering::Relaxed);
+    log::set_max_level(level.log_filter());
+}
+
+/// Watches the entry point, and the modules it imported, for hot reloading.
+fn script_watcher(sources: ModuleSources, entry_path: &Path) -> FileWatcher {
+    let mut watcher = FileWatcher::new(sources);
+    watcher.watch(entry_path);
+    watcher
+}
+
+/// Opens the file given on the command line to replay from, or record to.
+fn open_replay(game: &mut Game, run_args: &RunArgs, logger: &slog::Logger) -> std::io::Result<()> {
+    if let Some(path) = &run_args.replay {
+        info!(logger, "Replaying {}", path);
+        let replay = ReplayPlayer::open(path)?;
+        game.seed = replay.seed();
+        game.replay = Some(replay);
+    } else if let Some(path) = &run_args.record {
+        info!(logger, "Recording to {}", path);
+        game.recorder = Some(ReplayRecorder::create(path, game.seed)?);
+    }
 
     Ok(())
 }
@@ -64,44 +313,326 @@ fn app_root_dir() -> std::io::Result<PathBuf> {
     Ok(path_buf)
 }
 
+/// Path of the entry point script, which must exist on disk or in the archive.
+fn entry_point<'a>(
+    logger: &slog::Logger,
+    manifest: &'a Manifest,
+    archive: Option<&Rc<Archive>>,
+) -> Result<&'a Path, Box<dyn ::std::error::Error>> {
+    let entry_path = match &manifest.entry {
+        Some(entry) => entry.as_path(),
+        None => {
+            error!(
+                logger,
+                "Specify an entry point script, or run in a directory with a {}",
+                Manifest::FILE_NAME
+            );
+            return Err("No entry point".into());
+        }
+    };
+    info!(logger, "Entry point: {}", entry_path.display());
+    if !entry_path.exists() && archived_entry(archive, entry_path).is_none() {
+        error!(logger, "Entry point does not exist: {}", entry_path.display());
+        return Err("Entry point does not exist".into());
+    }
+
+    Ok(entry_path)
+}
+
+/// Reads the entry point script, from the archive when the game is packed.
+/// Relative imports are resolved against the entry point's directory.
+fn read_entry(entry_path: &Path, roots: &[PathBuf], archive: Option<&Rc<Archive>>) -> std::io::Result<LoadedModule> {
+    let (source, origin, entry_module) = match (archive, archived_entry(archive, entry_path)) {
+        (Some(archive), Some(name)) => (
+            archive.read_to_string(&name)?,
+            ModuleOrigin::Archive(name.clone()),
+            module_for_file(&[Path::new("")], Path::new(&name)),
+        ),
+        _ => (
+            fs::read_to_string(entry_path)?,
+            ModuleOrigin::File(entry_path.to_path_buf()),
+            module_for_file(roots, &fs::canonicalize(entry_path)?),
+        ),
+    };
+
+    Ok(match entry_module {
+        Some(entry_module) => LoadedModule {
+            name: entry_module.to_string().into(),
+            source: resolve_imports(&source, &entry_module, &builtin_modules()).0,
+            origin,
+        },
+        None => LoadedModule {
+            name: entry_path.to_string_lossy().into(),
+            source,
+            origin,
+        },
+    })
+}
+
+/// Interpret the entry point script, and hook the user's game into the engine.
+fn load_game(
+    vm: &mut WrenVm,
+    logger: &slog::Logger,
+    wren_logger: &slog::Logger,
+    entry_path: &Path,
+    roots: &[PathBuf],
+    archive: Option<&Rc<Archive>>,
+    windowed_context: Option<WindowedContext<PossiblyCurrent>>,
+    device: GraphicDevice,
+) -> Result<Game, Box<dyn ::std::error::Error>> {
+    // Source is dropped when loading is done.
+    // It's copied into Wren so no need to keep it in memory.
+    let entry = read_entry(entry_path, roots, archive)?;
+    // Interpreted under its module name, so scripts that import the
+    // entry point get the loaded module instead of running it again.
+    let interp_result = vm.interpret(&entry.name, &entry.source);
+    if let Err(err) = interp_result {
+        log_wren_error(wren_logger, &err);
+        return Err(err.into());
+    };
+
+    let init_result = vm.context_result(|ctx| {
+        // Compile built in shaders.
+        init_default_shaders(ctx, &device)?;
+
+        // Move graphics device into Wren VM.
+        let graphic_device_hooks = init_graphic_device(ctx, device);
+        init_game(ctx, logger.clone(), windowed_context, graphic_device_hooks)
+    });
+    if let Err(err) = &init_result {
+        log_wren_error(wren_logger, &err);
+    };
+
+    Ok(init_result?)
+}
+
+/// Compile the scripts reachable from the entry point without running the
+/// game, and print the problems found.
+fn check_project(
+    roots: &[PathBuf],
+    archive: Option<&Rc<Archive>>,
+    entry_path: &Path,
+    wren_logger: &slog::Logger,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let entry = read_entry(entry_path, roots, archive)?;
+    let mut loader = module_loader(roots, archive, ModuleSources::new());
+    let (modules, mut diagnostics) = collect_modules(entry, |name| loader.load_module(name));
+    diagnostics.extend(check_imports(&modules));
+
+    let loaded = ModuleSources::new();
+    let mut vm = build_vm(module_loader(roots, archive, loaded.clone()), ScriptOutput::Stdout);
+    if let Err(err) = load_builtins(&mut vm) {
+        log_wren_error(wren_logger, &err);
+        return Err(err.into());
+    };
+
+    // Scripts can use graphics types at the top level, which works without a window.
+    vm.context_result(|ctx| {
+        let device = GraphicDevice::headless(PhysicalSize::new(800, 600));
+        init_default_shaders(ctx, &device)?;
+        init_graphic_device(ctx, device);
+        Ok(())
+    })?;
+
+    diagnostics.extend(compile_modules(&mut vm, &modules, &loaded));
+
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    let checked = modules
+        .iter()
+        .filter(|module| module.origin != ModuleOrigin::Builtin)
+        .count();
+    println!("\n{} problems found, in {} modules", diagnostics.len(), checked);
+
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err("Check failed".into())
+    }
+}
+
+/// Run each test script in a fresh VM, and print a summary.
+fn run_tests(
+    app_root: &Path,
+    manifest: &Manifest,
+    wren_logger: &slog::Logger,
+    test_args: TestArgs,
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let module_dirs = module_roots(app_root, manifest)?;
+    // Without explicit paths, tests are found wherever modules are.
+    let test_paths = if test_args.paths.is_empty() {
+        find_tests(&module_dirs)?
+    } else {
+        find_tests(&test_args.paths)?
+    };
+    let builtins = builtin_modules();
+    let mut summary = TestSummary::default();
+
+    for test_path in &test_paths {
+        let mut vm = build_vm(
+            module_loader(&module_dirs, None, ModuleSources::new()),
+            ScriptOutput::Stdout,
+        );
+        // The runner calls into the test module after the script is loaded.
+        if let Err(err) = load_builtins(&mut vm).and_then(|_| preload_module(&mut vm, TEST_MODULE)) {
+            log_wren_error(wren_logger, &err);
+            return Err(err.into());
+        };
+
+        // Tests run without a window, but can still use graphics types.
+        vm.context_result(|ctx| {
+            let device = GraphicDevice::headless(PhysicalSize::new(800, 600));
+            init_default_shaders(ctx, &device)?;
+            init_graphic_device(ctx, device);
+            init_window_control(ctx)?;
+            Ok(())
+        })?;
+
+        run_test_file(&mut vm, test_path, &module_dirs, &builtins, &mut summary)?;
+    }
+
+    println!(
+        "\n{} passed, {} failed, {} broken, in {} test scripts",
+        summary.passed,
+        summary.failed,
+        summary.broken,
+        test_paths.len()
+    );
+
+    if summary.is_success() {
+        Ok(())
+    } else {
+        Err("Tests failed".into())
+    }
+}
+
 fn main() -> Result<(), Box<dyn ::std::error::Error>> {
+    // Application root directory.
+    let app_root = app_root_dir()?;
+    let cwd = env::current_dir()?;
+
+    // Command line arguments and project manifest. They configure logging,
+    // so they're read first, and errors are logged once it's set up.
+    let project = Command::parse(env::args().skip(1)).and_then(|command| {
+        let (mut manifest, archive) = load_project(&app_root, &cwd)?;
+        match &command {
+            Command::Run(run_args) => manifest.merge_args(run_args),
+            Command::Check(check_args) => {
+                if let Some(entry) = &check_args.entry {
+                    manifest.entry = Some(PathBuf::from(entry));
+                }
+            }
+            _ => {}
+        }
+        Ok((command, manifest, archive))
+    });
+
     // Logging
-    let decorator = slog_term::TermDecorator::new().build();
-    let drain = slog_term::FullFormat::new(decorator).build().fuse();
-    let drain = slog_async::Async::new(drain).build().fuse();
-    let root = slog::Logger::root(drain, o!());
+    let (log_level, log_format, log_file) = match &project {
+        Ok((_, manifest, _)) => (
+            manifest.log_level.unwrap_or(LogLevel::Debug),
+            manifest.log_format.unwrap_or_default(),
+            manifest.log_file.clone(),
+        ),
+        Err(_) => (LogLevel::Debug, LogFormat::default(), None),
+    };
+    let max_level = Arc::new(AtomicUsize::new(0));
+    let history = LogHistory::new(LogHistory::DEFAULT_CAPACITY);
+    let (root, log_file_err) =
+        match build_root_logger(log_format, log_file.as_deref(), max_level.clone(), history.clone()) {
+            Ok(root) => (root, None),
+            Err(err) => (
+                build_root_logger(LogFormat::Term, None, max_level.clone(), history.clone())?,
+                Some(err),
+            ),
+        };
     let logger = root.new(o!("lang" => "Rust"));
 
     let _scope_guard = slog_scope::set_global_logger(logger.clone());
-    let _log_guard = slog_stdlog::init_with_level(log::Level::Debug).unwrap();
+    let _log_guard = slog_stdlog::init_with_level(log::Level::Trace).unwrap();
+    set_log_level(&max_level, log_level);
 
-    // Application root directory.
-    let app_root = app_root_dir()?;
     info!(logger, "Executable directory: {}", app_root.to_string_lossy());
+    if let Some(err) = log_file_err {
+        error!(logger, "Failed to open log file: {}", err);
+    }
 
-    // Wren VM
+    // Wren logger for script output, and runtime and compiler errors.
     let wren_logger = root.new(o!("lang" => "Wren"));
-    let mut vm = WrenBuilder::new()
-        .with_module_loader(crate::io::WrenModuleLoader::from_root(app_root).with_root(std::env::current_dir()?))
-        .with_module(MATH_MODULE, bind_math)
-        .with_module(WINDOW_MODULE, bind_window)
-        .with_module(GRAPHICS_MODULE, |module| {
-            bind_graphic_device(module);
-            bind_graphics(module);
-        })
-        .with_module(COLLECTIONS_MODULE, |module| {
-            bind_collections(module);
-        })
-        .with_module(NOISE_MODULE, bind_noise)
-        .with_write_fn(move |msg| {
-            if msg != "\n" {
-                info!(wren_logger, "{}", msg)
-            }
-        })
-        .build();
 
-    // Wren logger for runtime and compiler errors.
-    let wren_logger = root.new(o!("lang" => "Wren"));
+    let (command, manifest, archive) = match project {
+        Ok(project) => project,
+        Err(err) => {
+            error!(logger, "{}", err);
+            return Err(err.into());
+        }
+    };
+    match &archive {
+        Some(archive) => info!(logger, "Packed game: {}", archive.path().display()),
+        None if cwd.join(Manifest::FILE_NAME).is_file() => {
+            info!(logger, "Project manifest: {}", Manifest::FILE_NAME)
+        }
+        None => {}
+    }
+    set_script_logger(
+        wren_logger.clone(),
+        LogFilter::new(log_level, manifest.log_modules.clone()),
+    );
+    set_asset_dirs(manifest.assets.clone());
+    set_asset_archive(archive.clone());
+    let roots = module_roots(&app_root, &manifest)?;
+    debug!(logger, "Module roots: {:?}", roots);
+
+    let run_args = match command {
+        Command::Run(run_args) => run_args,
+        Command::Repl => {
+            let mut vm = build_vm(
+                module_loader(&roots, archive.as_ref(), ModuleSources::new()),
+                ScriptOutput::Stdout,
+            );
+            if let Err(err) = load_builtins(&mut vm) {
+                log_wren_error(&wren_logger, &err);
+                return Err(err.into());
+            };
+
+            repl::run_repl(&mut vm)?;
+            return Ok(());
+        }
+        Command::Test(test_args) => return run_tests(&app_root, &manifest, &wren_logger, test_args),
+        Command::Pack(pack_args) => {
+            return match pack_project(&cwd, &manifest, &pack_args) {
+                Ok(output) => {
+                    info!(logger, "Packed game: {}", output.display());
+                    Ok(())
+                }
+                Err(err) => {
+                    error!(logger, "{}", err);
+                    Err(err.into())
+                }
+            };
+        }
+        Command::Check(_) => {
+            let entry_path = entry_point(&logger, &manifest, archive.as_ref())?;
+            return check_project(&roots, archive.as_ref(), entry_path, &wren_logger);
+        }
+    };
+
+    // Crash reports are only written for games that are played.
+    install_crash_reports(cwd.join(CRASH_DIR), history);
+
+    if run_args.profile.is_some() || run_args.profile_frames.is_some() {
+        let output = run_args.profile.as_deref().unwrap_or(Profiler::DEFAULT_OUTPUT);
+        info!(logger, "Profiling to {}", output);
+        enable_profiler(PathBuf::from(output), run_args.profile_frames);
+    }
+
+    // Wren VM
+    let sources = ModuleSources::new();
+    let mut vm = build_vm(
+        module_loader(&roots, archive.as_ref(), sources.clone()),
+        ScriptOutput::Log(wren_logger.clone()),
+    );
 
     // Builtin modules
     if let Err(err) = load_builtins(&mut vm) {
@@ -110,89 +641,166 @@ fn main() -> Result<(), Box<dyn ::std::error::Error>> {
     };
 
     // Validate the entry point exists
-    let args: Vec<String> = env::args().collect();
-    if args.len() <= 1 {
-        error!(logger, "Specify an entry point script.");
-        return Err(GersError::InvalidCmdArgs.into());
-    }
-
-    let script_entry = args[1].as_str();
-    info!(logger, "Entry point: {}", script_entry);
-    let entry_path = Path::new(script_entry);
-    if !entry_path.exists() {
-        error!(logger, "Entry point does not exist: {}", entry_path.display());
-        return Err("Entry point does not exist".into());
-    }
+    let entry_path = entry_point(&logger, &manifest, archive.as_ref())?;
 
     // Window configuration
     // TODO: Move the Bootstrap stuff to the game.wren class.
-    let mut window_conf: Option<WrenWindowConfig> = None;
-    vm.context(|ctx| {
-        info!(logger, "Get window configuration");
-
+    info!(logger, "Get window configuration");
+    let window_conf = vm.context_result(|ctx| {
         let receiver = ctx
-            .get_var("main", "Bootstrap")
+            .get_var(MAIN_MODULE, "Bootstrap")
             .expect("Failed to lookup Bootstrap class");
-        let func = FnSymbolRef::compile(ctx, "window()").unwrap();
+        let func = FnSymbolRef::compile(ctx, "window()")?;
         let call_ref = WrenCallRef::new(receiver, func);
 
-        window_conf = call_ref
-            .call::<_, Option<WrenWindowConfig>>(ctx, ())
-            .unwrap() // TODO: Will be WrenResult in future
-            .map(|c| c.borrow().clone());
+        // The bootstrap can be overridden, so its errors are the script's.
+        Ok(call_ref
+            .call::<_, Option<WrenWindowConfig>>(ctx, ())?
+            .map(|c| c.borrow().clone()))
     });
+    let window_conf = match window_conf {
+        Ok(window_conf) => window_conf,
+        Err(err) => {
+            log_wren_error(&wren_logger, &err);
+            return Err(err.into());
+        }
+    };
 
-    let conf = window_conf.unwrap_or_else(WrenWindowConfig::new);
+    let mut conf = window_conf.unwrap_or_else(WrenWindowConfig::new);
+    manifest.window.apply(&mut conf);
     debug!(logger, "{:?}", conf);
 
+    if run_args.headless {
+        info!(logger, "Running headless");
+
+        if run_args.watch {
+            warn!(logger, "Hot reloading is not supported when headless");
+        }
+
+        // Without a window there is no scale factor, so the
+        // logical size is used as the physical viewport.
+        let viewport_size = PhysicalSize::new(conf.size[0] as u32, conf.size[1] as u32);
+        let device = GraphicDevice::headless(viewport_size);
+
+        let mut game = load_game(
+            &mut vm,
+            &logger,
+            &wren_logger,
+            entry_path,
+            &roots,
+            archive.as_ref(),
+            None,
+            device,
+        )?;
+        game.window_conf = conf;
+        open_replay(&mut game, &run_args, &logger)?;
+        game.run_headless(
+            &mut vm,
+            HeadlessConfig {
+                frames: run_args.frames,
+                delta_time: Duration::from_secs_f64(run_args.delta_time),
+            },
+        )?;
+        finish_profiler();
+
+        return Ok(());
+    }
+
     // Create OpenGL context from window.
-    let event_loop = glutin::event_loop::EventLoop::new();
+    let mut event_loop = glutin::event_loop::EventLoop::new();
     let wb = WindowBuilder::new()
         .with_title(conf.title.clone())
         .with_inner_size(LogicalSize::new(conf.size[0], conf.size[1]));
     let windowed_context = ContextBuilder::new()
-        .with_vsync(false)
+        .with_vsync(conf.vsync)
         .with_gl(GlRequest::Specific(Api::OpenGl, (4, 5)))
         .with_gl_profile(GlProfile::Core)
         .build_windowed(wb, &event_loop)?;
     let windowed_context = unsafe { windowed_context.make_current().unwrap() };
     let device = unsafe { GraphicDevice::from_windowed_context(&windowed_context) };
+    set_opengl_info(device.opengl_info().as_ref());
+
+    // Devices of reloaded games share the queue, since resources of the
+    // old game are dropped along with its VM, after its device.
+    let destroy_queue = device.destroy_queue();
+
+    let mut game = load_game(
+        &mut vm,
+        &logger,
+        &wren_logger,
+        entry_path,
+        &roots,
+        archive.as_ref(),
+        Some(windowed_context),
+        device,
+    )?;
+
+    game.window_conf = conf;
+    open_replay(&mut game, &run_args, &logger)?;
 
-    // Useful code for when the engine needs to expose fullscreen
-    // modes to scripts.
-    // if let Some(monitor) = windowed_context.window().current_monitor() {
-    //     info!(logger, "Current Monitor:");
-    //     for mode in monitor.video_modes() {
-    //         info!(logger, "  VideoMode: size={:?}, bit_depth={}, refresh_rate={}", mode.size(), mode.bit_depth(), mode.refresh_rate());
-    //     }
-    // };
-
-    let mut game = {
-        // In block so source is dropped when loading is done.
-        // It's copied into Wren so no need to keep it in memory.
-        let source = fs::read_to_string(entry_path)?;
-        let interp_result = vm.interpret("core", &source);
-        if let Err(err) = interp_result {
+    if run_args.watch {
+        info!(logger, "Watching scripts for changes");
+        game.watcher = Some(script_watcher(sources, entry_path));
+    }
+
+    while game.run(&mut vm, &mut event_loop)? == RunExit::Reload {
+        info!(logger, "Reloading scripts");
+
+        // Scripts are loaded into a new VM, so the running game is
+        // unaffected when the changed scripts fail to compile.
+        let sources = ModuleSources::new();
+        let mut reload_vm = build_vm(
+            module_loader(&roots, archive.as_ref(), sources.clone()),
+            ScriptOutput::Log(wren_logger.clone()),
+        );
+
+        if let Err(err) = load_builtins(&mut reload_vm) {
             log_wren_error(&wren_logger, &err);
-            return Err(err.into());
+            continue;
+        }
+
+        // The new VM gets its own graphic device sharing the window's OpenGL context.
+        let device = unsafe { GraphicDevice::from_windowed_context(game.windowed_context.as_ref().unwrap()) }
+            .with_destroy_queue(destroy_queue.clone());
+
+        let mut reload_game = match load_game(
+            &mut reload_vm,
+            &logger,
+            &wren_logger,
+            entry_path,
+            &roots,
+            archive.as_ref(),
+            None,
+            device,
+        ) {
+            Ok(reload_game) => reload_game,
+            Err(_) => {
+                warn!(logger, "Reload failed, resuming running game");
+                continue;
+            }
         };
 
-        let init_result = vm.context_result(|ctx| {
-            // Compile built in shaders.
-            init_default_shaders(ctx, &device)?;
+        reload_game.reload_state = game.save_state(&mut vm).ok();
+        reload_game.window_conf = game.window_conf.clone();
+        reload_game.scale_factor = game.scale_factor;
+        reload_game.windowed_context = game.windowed_context.take();
+        reload_game.watcher = Some(script_watcher(sources, entry_path));
+        reload_game.seed = game.seed;
+        reload_game.recorder = game.recorder.take();
+        reload_game.replay = game.replay.take();
 
-            // Move graphics device into Wren VM.
-            let graphic_device_hooks = init_graphic_device(ctx, device);
-            init_game(ctx, logger.clone(), windowed_context, graphic_device_hooks)
+        // Release what the old game has already dropped.
+        vm.context(|ctx| {
+            if let Err(err) = game.graphic_hooks.maintain_handle.call::<_, ()>(ctx, ()) {
+                log_wren_error(&wren_logger, &err);
+            }
         });
-        if let Err(err) = &init_result {
-            log_wren_error(&wren_logger, &err);
-        };
-        init_result?
-    };
 
-    game.window_conf = conf;
-    game.run(&mut vm, event_loop)?;
+        // Handles of the old game must be released before its VM is dropped.
+        game = reload_game;
+        vm = reload_vm;
+    }
+    finish_profiler();
 
     Ok(())
 }
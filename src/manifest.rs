@@ -0,0 +1,387 @@
+//! Project manifest.
+//!
+//! A `gers.json` file in the working directory describes how to run
+//! the project, so the entry point and settings don't have to be
+//! given on the command line each time. Command line options take
+//! precedence over the manifest.
+//!
+//! ```json
+//! {
+//!   "entry": "src/main.wren",
+//!   "roots": ["lib"],
+//!   "window": { "size": [1024, 768], "title": "My Game", "vsync": true, "fixedStep": 60 },
+//!   "logLevel": "info",
+//!   "logModules": { "game.enemies": "trace" },
+//!   "logFormat": "json",
+//!   "logFile": "gers.log",
+//!   "assets": ["assets"]
+//! }
+//! ```
+use crate::{
+    cli::RunArgs,
+    errors::{GersError, GersResult},
+    game::FpsThrottlePolicy,
+    window::WrenWindowConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct Manifest {
+    /// Path to the entry point script.
+    pub entry: Option<PathBuf>,
+    /// Extra directories to search for modules, after the
+    /// executable and working directories.
+    pub roots: Vec<PathBuf>,
+    pub window: WindowManifest,
+    pub log_level: Option<LogLevel>,
+    /// Levels for the script modules, and their submodules, that log
+    /// with `gers.log`, overriding `log_level`.
+    pub log_modules: BTreeMap<String, LogLevel>,
+    pub log_format: Option<LogFormat>,
+    /// File that log output is appended to, instead of standard output.
+    pub log_file: Option<PathBuf>,
+    /// Directories to search for assets loaded with a relative path.
+    pub assets: Vec<PathBuf>,
+}
+
+/// Window settings, which take precedence over the
+/// configuration returned by `Bootstrap.window()`.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct WindowManifest {
+    pub size: Option<[f64; 2]>,
+    pub title: Option<String>,
+    pub vsync: Option<bool>,
+    /// Rate of fixed updates per second. Zero disables fixed updates.
+    pub fixed_step: Option<f64>,
+    /// Limit of fixed updates run in a single frame.
+    pub max_steps: Option<u32>,
+    /// Quit the game when Escape is pressed.
+    pub exit_on_escape: Option<bool>,
+    /// Frame rate cap. Zero leaves the frame rate uncapped.
+    pub target_fps: Option<u32>,
+    /// How the rest of a frame is waited out to reach the target frame rate.
+    pub throttle: Option<FpsThrottlePolicy>,
+    /// Append the frame rate to the window title. Disable when
+    /// the game shows it some other way, using `Game.stats`.
+    pub show_fps: Option<bool>,
+}
+
+impl Manifest {
+    pub const FILE_NAME: &'static str = "gers.json";
+
+    pub fn parse(source: &str) -> GersResult<Self> {
+        serde_json::from_str(source).map_err(|err| GersError::InvalidManifest(err.to_string()))
+    }
+
+    /// Reads the manifest in the given directory. Returns `None` when
+    /// the directory has no manifest.
+    ///
+    /// Relative paths in the manifest are resolved against the directory.
+    pub fn find<P: AsRef<Path>>(dir: P) -> GersResult<Option<Self>> {
+        let dir = dir.as_ref();
+        let path = dir.join(Self::FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let manifest = Self::parse(&fs::read_to_string(&path)?)?;
+        Ok(Some(manifest.with_base_dir(dir)))
+    }
+
+    fn with_base_dir(mut self, dir: &Path) -> Self {
+        self.entry = self.entry.map(|entry| dir.join(entry));
+        self.roots = self.roots.iter().map(|root| dir.join(root)).collect();
+        self.assets = self.assets.iter().map(|assets| dir.join(assets)).collect();
+        self.log_file = self.log_file.map(|log_file| dir.join(log_file));
+        self
+    }
+
+    /// Overrides the manifest with the options given on the command line.
+    pub fn merge_args(&mut self, run_args: &RunArgs) {
+        if let Some(entry) = &run_args.entry {
+            self.entry = Some(PathBuf::from(entry));
+        }
+        if !run_args.roots.is_empty() {
+            self.roots = run_args.roots.iter().map(PathBuf::from).collect();
+        }
+        if !run_args.assets.is_empty() {
+            self.assets = run_args.assets.iter().map(PathBuf::from).collect();
+        }
+        if run_args.size.is_some() {
+            self.window.size = run_args.size;
+        }
+        if run_args.title.is_some() {
+            self.window.title = run_args.title.clone();
+        }
+        if run_args.vsync.is_some() {
+            self.window.vsync = run_args.vsync;
+        }
+        if run_args.log_level.is_some() {
+            self.log_level = run_args.log_level;
+        }
+        for (module, level) in &run_args.log_modules {
+            self.log_modules.insert(module.clone(), *level);
+        }
+        if run_args.log_format.is_some() {
+            self.log_format = run_args.log_format;
+        }
+        if let Some(log_file) = &run_args.log_file {
+            self.log_file = Some(PathBuf::from(log_file));
+        }
+    }
+}
+
+impl WindowManifest {
+    /// Overrides the script's window configuration with the values
+    /// that were set.
+    pub fn apply(&self, conf: &mut WrenWindowConfig) {
+        if let Some([width, height]) = self.size {
+            conf.set_size(width, height);
+        }
+        if let Some(title) = &self.title {
+            conf.set_title(title.clone());
+        }
+        if let Some(vsync) = self.vsync {
+            conf.set_vsync(vsync);
+        }
+        if let Some(fixed_step) = self.fixed_step {
+            conf.set_fixed_step(fixed_step);
+        }
+        if let Some(max_steps) = self.max_steps {
+            conf.set_max_steps(max_steps);
+        }
+        if let Some(exit_on_escape) = self.exit_on_escape {
+            conf.set_exit_on_escape(exit_on_escape);
+        }
+        if let Some(target_fps) = self.target_fps {
+            conf.set_target_fps(target_fps);
+        }
+        if let Some(throttle) = self.throttle {
+            conf.throttle = throttle;
+        }
+        if let Some(show_fps) = self.show_fps {
+            conf.set_show_fps(show_fps);
+        }
+    }
+}
+
+/// Levels are ordered from least to most verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn slog_level(self) -> slog::Level {
+        match self {
+            LogLevel::Error => slog::Level::Error,
+            LogLevel::Warn => slog::Level::Warning,
+            LogLevel::Info => slog::Level::Info,
+            LogLevel::Debug => slog::Level::Debug,
+            LogLevel::Trace => slog::Level::Trace,
+        }
+    }
+
+    pub fn log_filter(self) -> log::LevelFilter {
+        match self {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = ParseLogLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(ParseLogLevelError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseLogLevelError(String);
+
+impl fmt::Display for ParseLogLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unknown log level: {}. Expected error, warn, info, debug or trace.",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseLogLevelError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines.
+    Term,
+    /// One JSON object per line, for log tooling.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Term
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = ParseLogFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "term" => Ok(LogFormat::Term),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(ParseLogFormatError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseLogFormatError(String);
+
+impl fmt::Display for ParseLogFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown log format: {}. Expected term or json.", self.0)
+    }
+}
+
+impl Error for ParseLogFormatError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cli::Command;
+
+    #[test]
+    fn test_parse() {
+        let manifest = Manifest::parse(
+            r#"{
+              "entry": "src/main.wren",
+              "roots": ["lib"],
+              "window": { "size": [800, 600], "vsync": true },
+              "logLevel": "warn",
+              "logModules": { "game.enemies": "trace" },
+              "logFormat": "json",
+              "logFile": "logs/game.log",
+              "assets": ["assets"]
+            }"#,
+        )
+        .unwrap()
+        .with_base_dir(Path::new("project"));
+
+        assert_eq!(manifest.entry, Some(PathBuf::from("project/src/main.wren")));
+        assert_eq!(manifest.roots, vec![PathBuf::from("project/lib")]);
+        assert_eq!(manifest.assets, vec![PathBuf::from("project/assets")]);
+        assert_eq!(manifest.log_level, Some(LogLevel::Warn));
+        assert_eq!(manifest.log_modules.get("game.enemies"), Some(&LogLevel::Trace));
+        assert_eq!(manifest.log_format, Some(LogFormat::Json));
+        assert_eq!(manifest.log_file, Some(PathBuf::from("project/logs/game.log")));
+        assert_eq!(
+            manifest.window,
+            WindowManifest {
+                size: Some([800.0, 600.0]),
+                vsync: Some(true),
+                ..WindowManifest::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply_window() {
+        let window = Manifest::parse(r#"{ "window": { "fixedStep": 30, "maxSteps": 2, "exitOnEscape": true } }"#)
+            .unwrap()
+            .window;
+        let mut conf = WrenWindowConfig::new();
+        window.apply(&mut conf);
+
+        assert_eq!(conf.fixed_step, Some(30.0));
+        assert_eq!(conf.max_steps, 2);
+        assert!(conf.exit_on_escape);
+
+        let window = Manifest::parse(r#"{ "window": { "targetFps": 60, "throttle": "hybrid" } }"#)
+            .unwrap()
+            .window;
+        window.apply(&mut conf);
+        assert_eq!(conf.target_fps, 60);
+        assert_eq!(conf.throttle, FpsThrottlePolicy::Hybrid);
+
+        assert!(conf.show_fps);
+        let window = Manifest::parse(r#"{ "window": { "showFps": false } }"#).unwrap().window;
+        window.apply(&mut conf);
+        assert!(!conf.show_fps);
+
+        // Zero turns off fixed updates enabled by the script.
+        let window = Manifest::parse(r#"{ "window": { "fixedStep": 0 } }"#).unwrap().window;
+        window.apply(&mut conf);
+        assert_eq!(conf.fixed_step, None);
+    }
+
+    #[test]
+    fn test_parse_err() {
+        assert!(Manifest::parse("{}").is_ok());
+        assert!(Manifest::parse(r#"{ "entyr": "main.wren" }"#).is_err());
+        assert!(Manifest::parse(r#"{ "logLevel": "loud" }"#).is_err());
+        assert!(Manifest::parse(r#"{ "logFormat": "xml" }"#).is_err());
+        assert!(Manifest::parse(r#"{ "window": { "size": [800] } }"#).is_err());
+        assert!(Manifest::parse(r#"{ "window": { "throttle": "spin" } }"#).is_err());
+    }
+
+    #[test]
+    fn test_merge_args() {
+        let mut manifest = Manifest::parse(
+            r#"{
+              "entry": "main.wren",
+              "roots": ["lib"],
+              "window": { "title": "Manifest", "vsync": true },
+              "logLevel": "warn",
+              "logModules": { "game": "info" }
+            }"#,
+        )
+        .unwrap();
+
+        let args = "--title Args --size 640x480 --root other --log-module game.ai=trace examples/basic.wren"
+            .split_whitespace()
+            .map(|arg| arg.to_string());
+        match Command::parse(args).unwrap() {
+            Command::Run(run_args) => manifest.merge_args(&run_args),
+            command => panic!("expected run command, got {:?}", command),
+        }
+
+        assert_eq!(manifest.entry, Some(PathBuf::from("examples/basic.wren")));
+        assert_eq!(manifest.roots, vec![PathBuf::from("other")]);
+        assert_eq!(manifest.window.title.as_deref(), Some("Args"));
+        assert_eq!(manifest.window.size, Some([640.0, 480.0]));
+        assert_eq!(manifest.window.vsync, Some(true));
+        assert_eq!(manifest.log_level, Some(LogLevel::Warn));
+        assert_eq!(manifest.log_modules.len(), 2);
+        assert_eq!(manifest.log_modules.get("game.ai"), Some(&LogLevel::Trace));
+    }
+}
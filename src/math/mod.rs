@@ -3,11 +3,11 @@ mod vector;
 pub const MATH_MODULE: &str = "gers.math";
 pub use self::vector::Vector2f;
 
-use rust_wren::{prelude::*, ModuleBuilder, WrenResult};
+use crate::io::BuiltinModules;
+use rust_wren::ModuleBuilder;
 
-pub fn register_math(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret(MATH_MODULE, include_str!("vector.wren"))?;
-    Ok(())
+pub fn register_math(builtins: &mut BuiltinModules) {
+    builtins.add(MATH_MODULE, include_str!("vector.wren"));
 }
 
 pub fn bind_math(module: &mut ModuleBuilder) {
@@ -6,12 +6,12 @@ pub const NOISE_MODULE: &str = "gers.noise";
 pub use self::poisson::{PoissonDisc, PoissonOptions};
 pub use self::voronoi::{Polygons, Voronoi2D};
 
-use rust_wren::{prelude::*, ModuleBuilder, WrenResult};
+use crate::io::BuiltinModules;
+use rust_wren::ModuleBuilder;
 
-pub fn register_noise(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret(NOISE_MODULE, include_str!("voronoi.wren"))?;
-    vm.interpret(NOISE_MODULE, include_str!("poisson.wren"))?;
-    Ok(())
+pub fn register_noise(builtins: &mut BuiltinModules) {
+    builtins.add(NOISE_MODULE, include_str!("voronoi.wren"));
+    builtins.add(NOISE_MODULE, include_str!("poisson.wren"));
 }
 
 pub fn bind_noise(module: &mut ModuleBuilder) {
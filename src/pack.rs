@@ -0,0 +1,178 @@
+//! Packing a project's scripts and assets into a single archive.
+use crate::{
+    cli::PackArgs,
+    errors::{GersError, GersResult},
+    io::{entry_name, Archive, ArchiveWriter},
+    manifest::Manifest,
+};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    iter,
+    path::{Path, PathBuf},
+};
+
+/// Packs the project in the given directory, returning the path
+/// of the written archive.
+///
+/// Scripts are collected from the project directory and the extra
+/// module roots, and named by their path in the first root they're
+/// found in, like the module loader would. Assets are named by their
+/// path in the asset directory. The archive also gets a manifest with
+/// the project's settings and the entry point.
+pub fn pack_project(project_dir: &Path, manifest: &Manifest, pack_args: &PackArgs) -> GersResult<PathBuf> {
+    let output = pack_args
+        .output
+        .as_ref()
+        .map(|output| project_dir.join(output))
+        .unwrap_or_else(|| project_dir.join(Archive::FILE_NAME));
+
+    let entry = pack_args
+        .entry
+        .as_ref()
+        .map(|entry| project_dir.join(entry))
+        .or_else(|| manifest.entry.clone())
+        .ok_or_else(|| {
+            GersError::Pack(format!(
+                "specify an entry point script, or add a {}",
+                Manifest::FILE_NAME
+            ))
+        })?;
+    let entry = entry
+        .strip_prefix(project_dir)
+        .ok()
+        .and_then(entry_name)
+        .ok_or_else(|| {
+            GersError::Pack(format!(
+                "entry point {} is not in the project directory",
+                entry.display()
+            ))
+        })?;
+
+    let mut writer = ArchiveWriter::new(pack_args.compress);
+
+    let roots = iter::once(project_dir).chain(manifest.roots.iter().map(PathBuf::as_path));
+    for root in roots {
+        add_dir(&mut writer, root, root, &output, &|path| {
+            path.extension().map(|ext| ext == "wren").unwrap_or(false)
+        })?;
+    }
+    for assets in &manifest.assets {
+        add_dir(&mut writer, assets, assets, &output, &|_| true)?;
+    }
+
+    if !writer.contains(&entry) {
+        return Err(GersError::Pack(format!("entry point {} was not found", entry)));
+    }
+
+    // Everything is relative to the archive now.
+    let packed = Manifest {
+        entry: Some(PathBuf::from(entry)),
+        roots: vec![],
+        window: manifest.window.clone(),
+        log_level: manifest.log_level,
+        log_modules: manifest.log_modules.clone(),
+        log_format: manifest.log_format,
+        log_file: None,
+        assets: vec![],
+    };
+    let packed = serde_json::to_vec_pretty(&packed).map_err(|err| GersError::Pack(err.to_string()))?;
+    writer.add(Manifest::FILE_NAME, packed)?;
+
+    writer.write(BufWriter::new(File::create(&output)?))?;
+
+    Ok(output)
+}
+
+/// Recursively adds the files accepted by the filter. Hidden
+/// directories and files are skipped, as is the archive itself.
+fn add_dir(
+    writer: &mut ArchiveWriter,
+    base: &Path,
+    dir: &Path,
+    output: &Path,
+    filter: &dyn Fn(&Path) -> bool,
+) -> GersResult<()> {
+    if !dir.is_dir() {
+        log::warn!("Skipping {}, which is not a directory", dir.display());
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        let is_hidden = path
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if is_hidden || path == output {
+            continue;
+        }
+
+        if path.is_dir() {
+            add_dir(writer, base, &path, output, filter)?;
+        } else if filter(&path) {
+            let name = path.strip_prefix(base).ok().and_then(entry_name).ok_or_else(|| {
+                GersError::Pack(format!("file name {} can't be stored in an archive", path.display()))
+            })?;
+
+            if writer.contains(&name) {
+                log::debug!("Skipping {}, which is shadowed by an earlier {}", path.display(), name);
+            } else {
+                log::debug!("Packing {}", name);
+                writer.add_file(name, &path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_project() {
+        let dir = std::env::temp_dir().join("gers_test_pack_project");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("lib").join("util")).unwrap();
+        fs::create_dir_all(dir.join("assets").join("gfx")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("src").join("main.wren"), "import \"util.math\"").unwrap();
+        fs::write(dir.join("lib").join("util").join("math.wren"), "").unwrap();
+        fs::write(dir.join("assets").join("gfx").join("player.png"), [1, 2, 3]).unwrap();
+        fs::write(dir.join(".git").join("hidden.wren"), "").unwrap();
+        fs::write(
+            dir.join(Manifest::FILE_NAME),
+            r#"{ "entry": "src/main.wren", "roots": ["lib"], "assets": ["assets"], "window": { "title": "Packed" } }"#,
+        )
+        .unwrap();
+
+        let manifest = Manifest::find(&dir).unwrap().unwrap();
+        let pack_args = PackArgs {
+            entry: None,
+            output: None,
+            compress: true,
+        };
+        let output = pack_project(&dir, &manifest, &pack_args).unwrap();
+        assert_eq!(output, dir.join(Archive::FILE_NAME));
+
+        let archive = Archive::open(&output).unwrap();
+        assert!(archive.contains("src/main.wren"));
+        assert!(archive.contains("util/math.wren"));
+        assert!(archive.contains("gfx/player.png"));
+        assert!(!archive.contains("hidden.wren"));
+        assert!(!archive.contains(".git/hidden.wren"));
+        assert_eq!(archive.read("gfx/player.png").unwrap(), vec![1, 2, 3]);
+
+        let packed = Manifest::parse(&archive.read_to_string(Manifest::FILE_NAME).unwrap()).unwrap();
+        assert_eq!(packed.entry, Some(PathBuf::from("src/main.wren")));
+        assert_eq!(packed.window.title.as_deref(), Some("Packed"));
+        assert!(packed.roots.is_empty());
+        assert!(packed.assets.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
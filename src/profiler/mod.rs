@@ -0,0 +1,272 @@
+//! Frame profiler.
+//!
+//! Records how long zones of engine and script code take, and
+//! writes them as Chrome trace events, which can be opened in
+//! `chrome://tracing` or Perfetto. Profiling is off unless it's
+//! enabled with `--profile`, in which case zones cost a clock read.
+mod trace;
+
+use self::trace::{write_trace, TraceEvent};
+use crate::io::BuiltinModules;
+use rust_wren::{prelude::*, ForeignError, ModuleBuilder};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufWriter},
+    path::PathBuf,
+    time::Instant,
+};
+
+pub const PROFILER_MODULE: &str = "gers.profiler";
+
+thread_local! {
+    /// Profiler for the main thread, which runs the game loop and the
+    /// Wren VM. Is `None` when profiling is disabled.
+    static PROFILER: RefCell<Option<Profiler>> = RefCell::new(None);
+}
+
+/// Starts profiling. The trace is written to the output file after
+/// the given number of frames, after which profiling stops, or when
+/// the game quits.
+pub fn enable_profiler(output: PathBuf, frames: Option<u64>) {
+    PROFILER.with(|profiler| *profiler.borrow_mut() = Some(Profiler::new(output, frames)));
+}
+
+fn with_profiler<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&mut Profiler) -> R,
+{
+    PROFILER.with(|profiler| profiler.borrow_mut().as_mut().map(f))
+}
+
+/// Starts a zone, which is recorded when the returned guard is dropped.
+pub fn zone(name: &'static str) -> Zone {
+    let enabled = PROFILER.with(|profiler| profiler.borrow().is_some());
+
+    Zone {
+        name,
+        start: if enabled { Some(Instant::now()) } else { None },
+        args: vec![],
+    }
+}
+
+/// Marks the start of a new frame, recording the previous one.
+///
+/// Writes the trace and stops profiling when the frame count is reached.
+pub fn end_frame() {
+    let done = with_profiler(Profiler::end_frame).unwrap_or(false);
+
+    if done {
+        if let Some(profiler) = PROFILER.with(|profiler| profiler.borrow_mut().take()) {
+            profiler.write_logged();
+        }
+    }
+}
+
+/// Writes the trace of a profile that didn't reach its frame count,
+/// and stops profiling.
+pub fn finish_profiler() {
+    if let Some(profiler) = PROFILER.with(|profiler| profiler.borrow_mut().take()) {
+        profiler.write_logged();
+    }
+}
+
+/// Scoped zone of engine code.
+#[must_use = "zone is recorded when dropped"]
+pub struct Zone {
+    name: &'static str,
+    /// Is `None` when profiling is disabled.
+    start: Option<Instant>,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Zone {
+    /// Adds an argument, shown with the zone in the trace viewer.
+    ///
+    /// The value is only formatted when profiling is enabled.
+    pub fn arg<V: ToString>(mut self, key: &'static str, value: V) -> Self {
+        if self.start.is_some() {
+            self.args.push((key, value.to_string()));
+        }
+        self
+    }
+}
+
+impl Drop for Zone {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let name = self.name;
+            let args = std::mem::take(&mut self.args);
+            with_profiler(|profiler| {
+                let mut event = profiler.event(Cow::Borrowed(name), "engine", start);
+                event.args.extend(args);
+                profiler.events.push(event);
+            });
+        }
+    }
+}
+
+pub struct Profiler {
+    output: PathBuf,
+    start: Instant,
+    events: Vec<TraceEvent>,
+    /// Zones begun by scripts that haven't ended yet, innermost last.
+    script_zones: Vec<(String, Instant)>,
+    frame_start: Option<Instant>,
+    frame_count: u64,
+    /// Number of frames to profile. When `None` the game is
+    /// profiled until it quits.
+    frames: Option<u64>,
+}
+
+impl Profiler {
+    pub const DEFAULT_OUTPUT: &'static str = "profile.json";
+
+    fn new(output: PathBuf, frames: Option<u64>) -> Self {
+        Self {
+            output,
+            start: Instant::now(),
+            events: vec![],
+            script_zones: vec![],
+            frame_start: None,
+            frame_count: 0,
+            frames,
+        }
+    }
+
+    /// Complete event from the start time until now.
+    fn event(&self, name: Cow<'static, str>, cat: &'static str, start: Instant) -> TraceEvent {
+        let ts = start.saturating_duration_since(self.start).as_secs_f64() * 1e6;
+        let dur = start.elapsed().as_secs_f64() * 1e6;
+        TraceEvent::complete(name, cat, ts, dur)
+    }
+
+    /// Returns true when the frame count was reached.
+    fn end_frame(&mut self) -> bool {
+        let now = Instant::now();
+
+        if let Some(frame_start) = self.frame_start {
+            let mut event = self.event(Cow::Borrowed("Frame"), "frame", frame_start);
+            event.args.insert("frame", self.frame_count.to_string());
+            self.events.push(event);
+            self.frame_count += 1;
+        }
+        self.frame_start = Some(now);
+
+        self.frames.map(|frames| self.frame_count >= frames).unwrap_or(false)
+    }
+
+    fn begin_script_zone(&mut self, name: String) {
+        self.script_zones.push((name, Instant::now()));
+    }
+
+    fn end_script_zone(&mut self) -> Result<(), ProfilerError> {
+        let (name, start) = self.script_zones.pop().ok_or(ProfilerError::NoOpenZone)?;
+        let event = self.event(Cow::Owned(name), "script", start);
+        self.events.push(event);
+        Ok(())
+    }
+
+    /// Writes the events recorded so far.
+    fn write(&self) -> io::Result<()> {
+        write_trace(BufWriter::new(File::create(&self.output)?), &self.events)
+    }
+
+    fn write_logged(&self) {
+        match self.write() {
+            Ok(()) => log::info!(
+                "Profile of {} frames written to {}",
+                self.frame_count,
+                self.output.display()
+            ),
+            Err(err) => log::error!("Failed to write profile to {}: {}", self.output.display(), err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ProfilerError {
+    /// Script ended a zone without beginning one.
+    NoOpenZone,
+}
+
+impl Error for ProfilerError {}
+
+impl fmt::Display for ProfilerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProfilerError::NoOpenZone => write!(f, "Profiler.end() called without a matching Profiler.begin()."),
+        }
+    }
+}
+
+pub fn register_profiler(builtins: &mut BuiltinModules) {
+    builtins.add(PROFILER_MODULE, include_str!("profiler.wren"));
+}
+
+pub fn bind_profiler(module: &mut ModuleBuilder) {
+    module.register::<WrenProfiler>();
+}
+
+#[wren_class(name = Profiler)]
+pub struct WrenProfiler;
+
+#[wren_methods]
+impl WrenProfiler {
+    #[construct]
+    fn new_() -> Self {
+        unimplemented!()
+    }
+
+    #[method(name = isEnabled_)]
+    fn is_enabled() -> bool {
+        PROFILER.with(|profiler| profiler.borrow().is_some())
+    }
+
+    #[method(name = begin_)]
+    fn begin(name: &str) {
+        with_profiler(|profiler| profiler.begin_script_zone(name.to_string()));
+    }
+
+    #[method(name = end_)]
+    fn end() -> Result<(), ForeignError> {
+        with_profiler(Profiler::end_script_zone)
+            .unwrap_or(Ok(()))
+            .map_err(|err| foreign_error!(err))
+    }
+
+    #[method(name = write_)]
+    fn write() -> Result<bool, ForeignError> {
+        match with_profiler(|profiler| profiler.write()) {
+            Some(result) => result.map(|_| true).map_err(|err| foreign_error!(err)),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_profiler() {
+        let mut profiler = Profiler::new(PathBuf::from(Profiler::DEFAULT_OUTPUT), Some(2));
+
+        assert!(!profiler.end_frame());
+        profiler.begin_script_zone("Player.update".to_string());
+        profiler.begin_script_zone("Player.move".to_string());
+        profiler.end_script_zone().unwrap();
+        profiler.end_script_zone().unwrap();
+        assert!(profiler.end_script_zone().is_err());
+        assert!(!profiler.end_frame());
+        assert!(profiler.end_frame());
+
+        let names: Vec<_> = profiler.events.iter().map(|event| event.name.as_ref()).collect();
+        assert_eq!(names, vec!["Player.move", "Player.update", "Frame", "Frame"]);
+        assert_eq!(profiler.events[3].args["frame"], "1");
+        assert!(profiler.events.iter().all(|event| event.ts >= 0.0 && event.dur >= 0.0));
+    }
+}
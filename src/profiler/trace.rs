@@ -0,0 +1,85 @@
+//! Chrome trace event format.
+//!
+//! See the [Trace Event Format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU)
+//! for the fields. Only complete events, which have a start time and
+//! duration, are written.
+use serde::Serialize;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+#[derive(Debug, Serialize)]
+pub struct TraceEvent {
+    pub name: Cow<'static, str>,
+    /// Category, for filtering in the viewer.
+    pub cat: &'static str,
+    /// Phase, which is `X` for complete events.
+    pub ph: &'static str,
+    /// Start time in microseconds, since the profiler started.
+    pub ts: f64,
+    /// Duration in microseconds.
+    pub dur: f64,
+    pub pid: u32,
+    pub tid: u32,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<&'static str, String>,
+}
+
+impl TraceEvent {
+    pub fn complete(name: Cow<'static, str>, cat: &'static str, ts: f64, dur: f64) -> Self {
+        Self {
+            name,
+            cat,
+            ph: "X",
+            ts,
+            dur,
+            // Everything is profiled on the main thread.
+            pid: 1,
+            tid: 1,
+            args: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceFile<'a> {
+    trace_events: &'a [TraceEvent],
+    display_time_unit: &'static str,
+}
+
+/// Writes the events as a JSON trace file.
+pub fn write_trace<W: Write>(writer: W, events: &[TraceEvent]) -> io::Result<()> {
+    let trace = TraceFile {
+        trace_events: events,
+        display_time_unit: "ms",
+    };
+    serde_json::to_writer(writer, &trace).map_err(io::Error::from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_trace() {
+        let mut event = TraceEvent::complete("WrenModuleLoader::load".into(), "engine", 10.0, 2.5);
+        event.args.insert("module", "game.player".to_string());
+        let events = vec![event, TraceEvent::complete("Frame".into(), "frame", 0.0, 16.0)];
+
+        let mut output = vec![];
+        write_trace(&mut output, &events).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"traceEvents":["#,
+                r#"{"name":"WrenModuleLoader::load","cat":"engine","ph":"X","ts":10.0,"dur":2.5,"pid":1,"tid":1,"args":{"module":"game.player"}},"#,
+                r#"{"name":"Frame","cat":"frame","ph":"X","ts":0.0,"dur":16.0,"pid":1,"tid":1}"#,
+                r#"],"displayTimeUnit":"ms"}"#
+            )
+        );
+    }
+}
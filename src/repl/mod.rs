@@ -0,0 +1,165 @@
+//! Interactive prompt for evaluating Wren code.
+use crate::{
+    errors::{format_wren_error, GersResult},
+    util::crate_version,
+};
+use rust_wren::{prelude::*, WrenError};
+use std::io::{self, BufRead, Write};
+
+/// Module that the entered code is interpreted in. Variables
+/// declared at the prompt persist between lines.
+pub const REPL_MODULE: &str = "repl";
+
+/// Read lines from standard input and evaluate them until end of input.
+///
+/// Builtin modules are expected to already be loaded into the VM.
+pub fn run_repl(vm: &mut WrenVm) -> GersResult<()> {
+    vm.interpret(REPL_MODULE, include_str!("repl.wren"))?;
+
+    println!("Gers v{} interactive prompt", crate_version().full);
+    println!("Press Ctrl+D to exit");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut source = String::new();
+
+    loop {
+        print!("{}", if source.is_empty() { "> " } else { "... " });
+        io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        source.push_str(&line);
+        source.push('\n');
+
+        // Keep reading until blocks, lists and strings are closed.
+        if is_incomplete(&source) {
+            continue;
+        }
+
+        evaluate(vm, source.trim());
+        source.clear();
+    }
+
+    println!();
+
+    Ok(())
+}
+
+/// Evaluate the source, printing the result if it's an expression.
+fn evaluate(vm: &mut WrenVm, source: &str) {
+    if source.is_empty() {
+        return;
+    }
+
+    // Statements are not valid as arguments, so failing to compile
+    // as an expression means the source is interpreted as is.
+    let result = match vm.interpret(REPL_MODULE, &format!("Repl_.print_({})", source)) {
+        Err(WrenError::CompileError(_)) => vm.interpret(REPL_MODULE, source),
+        result => result,
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", format_wren_error(&err));
+    }
+}
+
+/// Checks whether the source ends inside a block, string or comment.
+fn is_incomplete(source: &str) -> bool {
+    // Open brackets, and string interpolations which are
+    // closed by a parenthesis.
+    let mut brackets: Vec<char> = vec![];
+    let mut in_string = false;
+    let mut block_comments = 0;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if block_comments > 0 {
+            match (c, chars.peek()) {
+                ('*', Some('/')) => {
+                    chars.next();
+                    block_comments -= 1;
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    block_comments += 1;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if in_string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                '%' if chars.peek() == Some(&'(') => {
+                    chars.next();
+                    brackets.push('%');
+                    in_string = false;
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => brackets.push(c),
+            ')' => {
+                // Closing an interpolation resumes the string.
+                if brackets.pop() == Some('%') {
+                    in_string = true;
+                }
+            }
+            ']' | '}' => {
+                brackets.pop();
+            }
+            '/' => match chars.peek() {
+                Some('/') => {
+                    // Line comment runs to the end of the line.
+                    while chars.peek().map_or(false, |c| *c != '\n') {
+                        chars.next();
+                    }
+                }
+                Some('*') => {
+                    chars.next();
+                    block_comments += 1;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    !brackets.is_empty() || in_string || block_comments > 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        assert!(!is_incomplete("1 + 2"));
+        assert!(!is_incomplete("var a = [1, 2, {\"b\": 3}]"));
+        assert!(!is_incomplete("class Foo {\n  construct new() {}\n}\n"));
+        assert!(!is_incomplete("\"a %(\"b\") c\""));
+        assert!(!is_incomplete("\"{\\\"\""));
+        assert!(!is_incomplete("// {"));
+        assert!(!is_incomplete("/* /* { */ */"));
+    }
+
+    #[test]
+    fn test_incomplete() {
+        assert!(is_incomplete("class Foo {\n"));
+        assert!(is_incomplete("var a = [1,\n"));
+        assert!(is_incomplete("System.print(\"a %(1 + \n"));
+        assert!(is_incomplete("\"abc"));
+        assert!(is_incomplete("/* /* */"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Test runner for Wren scripts.
+use crate::{
+    errors::{format_wren_error, GersResult},
+    io::{module_for_file, resolve_imports, BuiltinModules},
+};
+use rust_wren::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const TEST_MODULE: &str = "gers.test";
+
+/// File name suffix of test scripts.
+const TEST_FILE_SUFFIX: &str = "_test.wren";
+
+pub fn register_test(builtins: &mut BuiltinModules) {
+    builtins.add(TEST_MODULE, include_str!("test.wren"));
+}
+
+/// Results of all test scripts that were run.
+#[derive(Debug, Default)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    /// Test scripts that failed to compile, or aborted
+    /// before their test cases were run.
+    pub broken: usize,
+}
+
+impl TestSummary {
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.broken == 0
+    }
+}
+
+/// Recursively search the given directories for test scripts.
+///
+/// Hidden directories are skipped. The returned paths are
+/// sorted and free of duplicates, in case the directories overlap.
+pub fn find_tests<P>(roots: &[P]) -> GersResult<Vec<PathBuf>>
+where
+    P: AsRef<Path>,
+{
+    let mut paths = vec![];
+
+    for root in roots {
+        if root.as_ref().is_dir() {
+            find_tests_in(root.as_ref(), &mut paths)?;
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+
+    Ok(paths)
+}
+
+fn find_tests_in(dir: &Path, paths: &mut Vec<PathBuf>) -> GersResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+
+        if file_name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            find_tests_in(&path, paths)?;
+        } else if file_name.ends_with(TEST_FILE_SUFFIX) {
+            paths.push(path.canonicalize()?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Interpret a test script, then run each of the test cases
+/// it declared, printing the results.
+///
+/// The VM is expected to be freshly built with builtin modules
+/// loaded, so scripts can't affect each other.
+pub fn run_test_file(
+    vm: &mut WrenVm,
+    path: &Path,
+    roots: &[PathBuf],
+    builtins: &BuiltinModules,
+    summary: &mut TestSummary,
+) -> GersResult<()> {
+    let module = path.to_string_lossy();
+    let source = fs::read_to_string(path)?;
+
+    // Relative imports are resolved against the script's directory.
+    let source = match module_for_file(roots, &fs::canonicalize(path)?) {
+        Some(test_module) => resolve_imports(&source, &test_module, builtins).0,
+        None => source,
+    };
+
+    println!("{}", module);
+
+    if let Err(err) = vm.interpret(&module, &source) {
+        println!("  error: script failed to load");
+        print_indented(&format_wren_error(&err));
+        summary.broken += 1;
+        return Ok(());
+    }
+
+    let (count, name, run) = vm.context_result(|ctx| {
+        let count = ctx.make_call_ref(TEST_MODULE, "Test", "count_")?;
+        let count = count.call::<_, f64>(ctx, ())? as usize;
+        let name = ctx.make_call_ref(TEST_MODULE, "Test", "name_(_)")?.leak()?;
+        let run = ctx.make_call_ref(TEST_MODULE, "Test", "run_(_)")?.leak()?;
+        Ok((count, name, run))
+    })?;
+
+    for index in 0..count {
+        let name = vm.context_result(|ctx| name.call::<_, String>(ctx, index as f64))?;
+        let result = vm.context_result(|ctx| run.call::<_, ()>(ctx, index as f64));
+
+        match result {
+            Ok(_) => {
+                println!("  ok   {}", name);
+                summary.passed += 1;
+            }
+            Err(err) => {
+                println!("  FAIL {}", name);
+                print_indented(&format_wren_error(&err));
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_indented(message: &str) {
+    for line in message.lines() {
+        println!("       {}", line);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_tests() {
+        let dir = std::env::temp_dir().join("gers_test_find_tests");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::create_dir_all(dir.join(".hidden")).unwrap();
+        fs::write(dir.join("math_test.wren"), "").unwrap();
+        fs::write(dir.join("math.wren"), "").unwrap();
+        fs::write(dir.join("nested").join("noise_test.wren"), "").unwrap();
+        fs::write(dir.join(".hidden").join("hidden_test.wren"), "").unwrap();
+
+        // Overlapping roots don't cause duplicates.
+        let paths = find_tests(&[dir.clone(), dir.join("nested")]).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        assert_eq!(
+            paths,
+            vec![dir.join("math_test.wren"), dir.join("nested").join("noise_test.wren")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
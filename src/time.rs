@@ -0,0 +1,7 @@
+use crate::io::BuiltinModules;
+
+pub const TIME_MODULE: &str = "gers.time";
+
+pub fn register_time(builtins: &mut BuiltinModules) {
+    builtins.add(TIME_MODULE, include_str!("time.wren"));
+}
@@ -0,0 +1,240 @@
+//! Easing functions, mapping the linear progress of a tween
+//! to an eased progress.
+//!
+//! Based on the equations by Robert Penner, as listed on [easings.net](https://easings.net).
+use std::{
+    error::Error,
+    f64::consts::PI,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Easing {
+    /// Eases the progress, which is expected to be between 0 and 1.
+    ///
+    /// Elastic and back easing overshoot, so they return values
+    /// outside of that range part of the way.
+    pub fn apply(self, t: f64) -> f64 {
+        use Easing::*;
+
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Linear => t,
+            QuadIn => t * t,
+            QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            CubicIn => t * t * t,
+            CubicOut => 1.0 - (1.0 - t).powi(3),
+            CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2.0_f64.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * ELASTIC).sin()
+                }
+            }
+            ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0_f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC).sin() + 1.0
+                }
+            }
+            ElasticInOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    -(2.0_f64.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin()) / 2.0
+                } else {
+                    2.0_f64.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin() / 2.0 + 1.0
+                }
+            }
+            BounceIn => 1.0 - bounce_out(1.0 - t),
+            BounceOut => bounce_out(t),
+            BounceInOut => {
+                if t < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+                }
+            }
+            BackIn => BACK_C3 * t * t * t - BACK_C1 * t * t,
+            BackOut => 1.0 + BACK_C3 * (t - 1.0).powi(3) + BACK_C1 * (t - 1.0).powi(2),
+            BackInOut => {
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((BACK_C2 + 1.0) * 2.0 * t - BACK_C2) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((BACK_C2 + 1.0) * (t * 2.0 - 2.0) + BACK_C2) + 2.0) / 2.0
+                }
+            }
+        }
+    }
+
+    /// Name used by scripts.
+    pub fn name(self) -> &'static str {
+        use Easing::*;
+
+        match self {
+            Linear => "linear",
+            QuadIn => "quadIn",
+            QuadOut => "quadOut",
+            QuadInOut => "quadInOut",
+            CubicIn => "cubicIn",
+            CubicOut => "cubicOut",
+            CubicInOut => "cubicInOut",
+            ElasticIn => "elasticIn",
+            ElasticOut => "elasticOut",
+            ElasticInOut => "elasticInOut",
+            BounceIn => "bounceIn",
+            BounceOut => "bounceOut",
+            BounceInOut => "bounceInOut",
+            BackIn => "backIn",
+            BackOut => "backOut",
+            BackInOut => "backInOut",
+        }
+    }
+
+    pub const ALL: [Easing; 16] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+    ];
+}
+
+impl FromStr for Easing {
+    type Err = ParseEasingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Easing::ALL
+            .iter()
+            .copied()
+            .find(|easing| easing.name() == s)
+            .ok_or_else(|| ParseEasingError(s.to_string()))
+    }
+}
+
+const ELASTIC: f64 = (2.0 * PI) / 3.0;
+const ELASTIC_IN_OUT: f64 = (2.0 * PI) / 4.5;
+const BACK_C1: f64 = 1.70158;
+const BACK_C2: f64 = BACK_C1 * 1.525;
+const BACK_C3: f64 = BACK_C1 + 1.0;
+
+fn bounce_out(t: f64) -> f64 {
+    const N1: f64 = 7.5625;
+    const D1: f64 = 2.75;
+
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseEasingError(String);
+
+impl Display for ParseEasingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown easing: {}", self.0)
+    }
+}
+
+impl Error for ParseEasingError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_end_points() {
+        for easing in Easing::ALL.iter() {
+            assert!(easing.apply(0.0).abs() < 1e-9, "{} at 0", easing.name());
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{} at 1", easing.name());
+        }
+    }
+
+    #[test]
+    fn test_in_out_symmetry() {
+        for easing in &[
+            Easing::QuadInOut,
+            Easing::CubicInOut,
+            Easing::BounceInOut,
+            Easing::BackInOut,
+        ] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-9, "{} at 0.5", easing.name());
+            assert!(
+                (easing.apply(0.25) + easing.apply(0.75) - 1.0).abs() < 1e-9,
+                "{} is not symmetric",
+                easing.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_overshoot() {
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+        assert!(Easing::ElasticOut.apply(0.1) > 1.0);
+    }
+
+    #[test]
+    fn test_parse() {
+        for easing in Easing::ALL.iter() {
+            assert_eq!(easing.name().parse::<Easing>().unwrap(), *easing);
+        }
+        assert!("quad".parse::<Easing>().is_err());
+    }
+}
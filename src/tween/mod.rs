@@ -0,0 +1,32 @@
+mod easing;
+
+pub const TWEEN_MODULE: &str = "gers.tween";
+pub use self::easing::Easing;
+
+use crate::io::BuiltinModules;
+use rust_wren::{prelude::*, ForeignError, ModuleBuilder};
+
+pub fn register_tween(builtins: &mut BuiltinModules) {
+    builtins.add(TWEEN_MODULE, include_str!("tween.wren"));
+}
+
+pub fn bind_tween(module: &mut ModuleBuilder) {
+    module.register::<WrenEasing>();
+}
+
+/// Easing function looked up by name, so scripts can apply it
+/// without evaluating the equations in Wren.
+#[wren_class(name = Easing)]
+pub struct WrenEasing(Easing);
+
+#[wren_methods]
+impl WrenEasing {
+    #[construct]
+    fn new(name: &str) -> Result<Self, ForeignError> {
+        name.parse().map(WrenEasing).map_err(|err| foreign_error!(err))
+    }
+
+    fn apply(&self, t: f64) -> f64 {
+        self.0.apply(t)
+    }
+}
@@ -2,7 +2,7 @@ use std::env;
 
 pub fn crate_version() -> Version {
     Version {
-        major: env!("CARGO_PKG_VERSION_MAjOR")
+        major: env!("CARGO_PKG_VERSION_MAJOR")
             .parse::<i32>()
             .expect("Parse version major failed"),
         minor: env!("CARGO_PKG_VERSION_MINOR")
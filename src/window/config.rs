@@ -1,11 +1,30 @@
-use crate::util::crate_version;
-use rust_wren::prelude::*;
+use crate::{
+    game::{FixedTimestep, FpsThrottle, FpsThrottlePolicy},
+    util::crate_version,
+};
+use rust_wren::{prelude::*, ForeignError};
 
 #[wren_class(name = WindowConf)]
 #[derive(Debug, Clone)]
 pub struct WrenWindowConfig {
     pub size: [f64; 2],
     pub title: String,
+    /// Rate of fixed updates per second. When `None` the game
+    /// updates once per frame with a variable delta time.
+    pub fixed_step: Option<f64>,
+    /// Maximum number of fixed updates to catch up on in a single frame.
+    pub max_steps: u32,
+    /// Pressing Escape exits the game. Off by default so
+    /// scripts can use Escape for menus.
+    pub exit_on_escape: bool,
+    /// Synchronise buffer swaps with the monitor's refresh rate.
+    pub vsync: bool,
+    /// Frame rate cap. Zero leaves the frame rate uncapped.
+    pub target_fps: u32,
+    /// How the remainder of a frame is waited out to reach the target frame rate.
+    pub throttle: FpsThrottlePolicy,
+    /// Append the frame rate to the window title.
+    pub show_fps: bool,
 }
 
 #[wren_methods]
@@ -15,6 +34,13 @@ impl WrenWindowConfig {
         Self {
             size: [512., 512.],
             title: format!("Game Engine v{}", crate_version().full),
+            fixed_step: None,
+            max_steps: FixedTimestep::DEFAULT_MAX_STEPS,
+            exit_on_escape: false,
+            vsync: false,
+            target_fps: Self::DEFAULT_TARGET_FPS,
+            throttle: FpsThrottlePolicy::Off,
+            show_fps: true,
         }
     }
 
@@ -25,4 +51,52 @@ impl WrenWindowConfig {
     pub fn set_title(&mut self, title: String) {
         self.title = title;
     }
+
+    pub fn set_fixed_step(&mut self, updates_per_second: f64) {
+        self.fixed_step = if updates_per_second.is_finite() && updates_per_second > 0.0 {
+            Some(updates_per_second)
+        } else {
+            None
+        };
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    pub fn set_exit_on_escape(&mut self, exit_on_escape: bool) {
+        self.exit_on_escape = exit_on_escape;
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.vsync = vsync;
+    }
+
+    pub fn set_target_fps(&mut self, target_fps: u32) {
+        self.target_fps = target_fps;
+    }
+
+    pub fn set_show_fps(&mut self, show_fps: bool) {
+        self.show_fps = show_fps;
+    }
+
+    pub fn set_throttle(&mut self, policy: &str) -> Result<(), ForeignError> {
+        self.throttle = policy.parse().map_err(|err| foreign_error!(err))?;
+        Ok(())
+    }
+}
+
+impl WrenWindowConfig {
+    pub const DEFAULT_TARGET_FPS: u32 = 144;
+
+    /// Creates the frame rate limiter.
+    pub fn fps_throttle(&self) -> FpsThrottle {
+        FpsThrottle::new(self.target_fps, self.throttle)
+    }
+
+    /// Creates the fixed update accumulator, if fixed updates are enabled.
+    pub fn timestep(&self) -> Option<FixedTimestep> {
+        self.fixed_step
+            .and_then(|rate| FixedTimestep::from_rate(rate, self.max_steps))
+    }
 }
@@ -0,0 +1,644 @@
+//! Runtime control of the window by scripts.
+//!
+//! The window is owned by the game, out of reach of the foreign
+//! methods, so script requests are queued and applied by the game
+//! loop once the script's hooks return. The state scripts read is
+//! updated as requests are made, and copied from the window when
+//! it's resized or moved.
+use crate::window::WrenWindowConfig;
+use glutin::{
+    dpi::{LogicalPosition, LogicalSize},
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window},
+};
+use rust_wren::{prelude::*, ForeignError};
+use std::{cell::RefCell, error::Error, fmt, rc::Rc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// Window covers a monitor without changing its video mode.
+    Borderless,
+    /// Window takes over a monitor with the chosen video mode.
+    Exclusive,
+}
+
+impl FullscreenMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            FullscreenMode::Windowed => "windowed",
+            FullscreenMode::Borderless => "borderless",
+            FullscreenMode::Exclusive => "exclusive",
+        }
+    }
+}
+
+/// Change to the window requested by a script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowCommand {
+    Windowed,
+    /// Borderless fullscreen on the monitor with the index, or
+    /// the window's current monitor.
+    Borderless(Option<usize>),
+    /// Exclusive fullscreen with a video mode, by the index of
+    /// the monitor and the index of the mode in its list.
+    Exclusive {
+        monitor: usize,
+        mode: usize,
+    },
+    /// Logical inner size.
+    Resize([f64; 2]),
+    /// Logical position of the top left corner, including decorations.
+    Move([f64; 2]),
+    Title(String),
+    Minimize(bool),
+    Maximize(bool),
+    CursorVisible(bool),
+    CursorGrab(bool),
+}
+
+impl WindowCommand {
+    /// Applies the change to the window. Monitors are looked up
+    /// again, since they may have been disconnected.
+    pub fn apply(&self, window: &Window) -> Result<(), WindowError> {
+        match self {
+            WindowCommand::Windowed => window.set_fullscreen(None),
+            WindowCommand::Borderless(None) => window.set_fullscreen(Some(Fullscreen::Borderless(None))),
+            WindowCommand::Borderless(Some(monitor)) => {
+                let handle = window
+                    .available_monitors()
+                    .nth(*monitor)
+                    .ok_or(WindowError::NoMonitor(*monitor))?;
+                window.set_fullscreen(Some(Fullscreen::Borderless(Some(handle))));
+            }
+            WindowCommand::Exclusive { monitor, mode } => {
+                let handle = window
+                    .available_monitors()
+                    .nth(*monitor)
+                    .ok_or(WindowError::NoMonitor(*monitor))?;
+                let video_mode = handle.video_modes().nth(*mode).ok_or(WindowError::NoVideoMode {
+                    monitor: *monitor,
+                    mode: *mode,
+                })?;
+                window.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
+            }
+            WindowCommand::Resize([width, height]) => window.set_inner_size(LogicalSize::new(*width, *height)),
+            WindowCommand::Move([x, y]) => window.set_outer_position(LogicalPosition::new(*x, *y)),
+            WindowCommand::Title(title) => window.set_title(title),
+            WindowCommand::Minimize(minimized) => window.set_minimized(*minimized),
+            WindowCommand::Maximize(maximized) => window.set_maximized(*maximized),
+            WindowCommand::CursorVisible(visible) => window.set_cursor_visible(*visible),
+            WindowCommand::CursorGrab(grab) => window
+                .set_cursor_grab(*grab)
+                .map_err(|err| WindowError::CursorGrab(err.to_string()))?,
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoModeInfo {
+    pub size: [u32; 2],
+    pub bit_depth: u16,
+    pub refresh_rate: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: String,
+    /// Physical size in pixels.
+    pub size: [u32; 2],
+    /// Physical position on the desktop.
+    pub position: [i32; 2],
+    pub scale_factor: f64,
+    pub video_modes: Vec<VideoModeInfo>,
+}
+
+impl MonitorInfo {
+    pub fn from_handle(handle: &MonitorHandle) -> Self {
+        let size = handle.size();
+        let position = handle.position();
+
+        Self {
+            name: handle.name().unwrap_or_default(),
+            size: [size.width, size.height],
+            position: [position.x, position.y],
+            scale_factor: handle.scale_factor(),
+            video_modes: handle
+                .video_modes()
+                .map(|mode| VideoModeInfo {
+                    size: [mode.size().width, mode.size().height],
+                    bit_depth: mode.bit_depth(),
+                    refresh_rate: mode.refresh_rate(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Window state as scripts see it, and the changes they requested.
+#[derive(Debug)]
+pub struct WindowControl {
+    /// Logical inner size.
+    pub size: [f64; 2],
+    /// Logical position of the top left corner, including decorations.
+    pub position: [f64; 2],
+    pub scale_factor: f64,
+    pub title: String,
+    pub fullscreen: FullscreenMode,
+    // The window can't be asked whether it's minimized or
+    // maximized, so these are the last requested states.
+    pub minimized: bool,
+    pub maximized: bool,
+    pub cursor_visible: bool,
+    pub cursor_grabbed: bool,
+    pub monitors: Vec<MonitorInfo>,
+    pub current_monitor: Option<usize>,
+    pub primary_monitor: Option<usize>,
+    commands: Vec<WindowCommand>,
+}
+
+impl WindowControl {
+    pub fn new() -> Self {
+        Self {
+            size: [0.0, 0.0],
+            position: [0.0, 0.0],
+            scale_factor: 1.0,
+            title: String::new(),
+            fullscreen: FullscreenMode::Windowed,
+            minimized: false,
+            maximized: false,
+            cursor_visible: true,
+            cursor_grabbed: false,
+            monitors: vec![],
+            current_monitor: None,
+            primary_monitor: None,
+            commands: vec![],
+        }
+    }
+
+    /// Takes the size and title the window was created with.
+    pub fn configure(&mut self, conf: &WrenWindowConfig) {
+        self.size = conf.size;
+        self.title = conf.title.clone();
+    }
+
+    /// Copies the state of the window, and the monitors it can be
+    /// placed on.
+    ///
+    /// Enumerating the video modes of every monitor is slow, so this
+    /// is only done when monitors are likely to have changed.
+    pub fn refresh(&mut self, window: &Window) {
+        let handles: Vec<MonitorHandle> = window.available_monitors().collect();
+        self.monitors = handles.iter().map(MonitorInfo::from_handle).collect();
+        self.primary_monitor = window
+            .primary_monitor()
+            .and_then(|primary| handles.iter().position(|handle| *handle == primary));
+
+        self.refresh_geometry(window);
+    }
+
+    /// Copies the size, position and fullscreen mode of the window,
+    /// which change as it's resized and moved.
+    pub fn refresh_geometry(&mut self, window: &Window) {
+        self.scale_factor = window.scale_factor();
+        let size = window.inner_size().to_logical::<f64>(self.scale_factor);
+        self.size = [size.width, size.height];
+        if let Ok(position) = window.outer_position() {
+            let position = position.to_logical::<f64>(self.scale_factor);
+            self.position = [position.x, position.y];
+        }
+        self.refresh_fullscreen(window);
+
+        // Matched against the known monitors, without enumerating them again.
+        self.current_monitor = window.current_monitor().and_then(|current| {
+            let name = current.name().unwrap_or_default();
+            let position = current.position();
+            self.monitors
+                .iter()
+                .position(|info| info.name == name && info.position == [position.x, position.y])
+        });
+    }
+
+    /// Copies the fullscreen mode of the window.
+    ///
+    /// Unlike the size and position, which some platforms only update
+    /// once the window has handled the request, this is known as soon
+    /// as it's set, so a request that failed is shown to scripts.
+    pub fn refresh_fullscreen(&mut self, window: &Window) {
+        self.fullscreen = match window.fullscreen() {
+            None => FullscreenMode::Windowed,
+            Some(Fullscreen::Borderless(_)) => FullscreenMode::Borderless,
+            Some(Fullscreen::Exclusive(_)) => FullscreenMode::Exclusive,
+        };
+    }
+
+    /// Queues a change to the window, which scripts see immediately.
+    pub fn push(&mut self, command: WindowCommand) -> Result<(), WindowError> {
+        match &command {
+            WindowCommand::Windowed => self.fullscreen = FullscreenMode::Windowed,
+            WindowCommand::Borderless(monitor) => {
+                if let Some(monitor) = monitor {
+                    self.monitor(*monitor)?;
+                }
+                self.fullscreen = FullscreenMode::Borderless;
+            }
+            WindowCommand::Exclusive { monitor, mode } => {
+                let video_mode = self.video_mode(*monitor, *mode)?;
+                self.size = [video_mode.size[0] as f64, video_mode.size[1] as f64];
+                self.fullscreen = FullscreenMode::Exclusive;
+            }
+            WindowCommand::Resize(size) => {
+                if !size.iter().all(|v| v.is_finite() && *v > 0.0) {
+                    return Err(WindowError::InvalidSize(*size));
+                }
+                self.size = *size;
+            }
+            WindowCommand::Move(position) => {
+                if !position.iter().all(|v| v.is_finite()) {
+                    return Err(WindowError::InvalidPosition(*position));
+                }
+                self.position = *position;
+            }
+            WindowCommand::Title(title) => self.title = title.clone(),
+            WindowCommand::Minimize(minimized) => self.minimized = *minimized,
+            WindowCommand::Maximize(maximized) => self.maximized = *maximized,
+            WindowCommand::CursorVisible(visible) => self.cursor_visible = *visible,
+            WindowCommand::CursorGrab(grab) => self.cursor_grabbed = *grab,
+        }
+
+        self.commands.push(command);
+
+        Ok(())
+    }
+
+    /// Takes the queued changes, in the order they were requested.
+    pub fn take_commands(&mut self) -> Vec<WindowCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    pub fn monitor(&self, monitor: usize) -> Result<&MonitorInfo, WindowError> {
+        self.monitors.get(monitor).ok_or(WindowError::NoMonitor(monitor))
+    }
+
+    pub fn video_mode(&self, monitor: usize, mode: usize) -> Result<&VideoModeInfo, WindowError> {
+        self.monitor(monitor)?
+            .video_modes
+            .get(mode)
+            .ok_or(WindowError::NoVideoMode { monitor, mode })
+    }
+}
+
+impl Default for WindowControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub enum WindowError {
+    /// Monitor or video mode index that isn't a whole, positive number.
+    InvalidIndex(f64),
+    /// Window size that isn't finite and greater than zero.
+    InvalidSize([f64; 2]),
+    /// Window position that isn't finite.
+    InvalidPosition([f64; 2]),
+    NoMonitor(usize),
+    NoVideoMode {
+        monitor: usize,
+        mode: usize,
+    },
+    CursorGrab(String),
+}
+
+impl Error for WindowError {}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowError::InvalidIndex(index) => write!(f, "Invalid monitor or video mode index {}.", index),
+            WindowError::InvalidSize([width, height]) => write!(f, "Invalid window size {}x{}.", width, height),
+            WindowError::InvalidPosition([x, y]) => write!(f, "Invalid window position {}, {}.", x, y),
+            WindowError::NoMonitor(monitor) => write!(f, "No monitor with index {}.", monitor),
+            WindowError::NoVideoMode { monitor, mode } => {
+                write!(f, "Monitor {} has no video mode with index {}.", monitor, mode)
+            }
+            WindowError::CursorGrab(message) => write!(f, "Failed to grab cursor: {}", message),
+        }
+    }
+}
+
+/// Converts a monitor or video mode index from a script.
+fn parse_index(value: f64) -> Result<usize, WindowError> {
+    if value >= 0.0 && value.fract() == 0.0 && value <= u32::MAX as f64 {
+        Ok(value as usize)
+    } else {
+        Err(WindowError::InvalidIndex(value))
+    }
+}
+
+/// Script view of the window control, shared with the game loop
+/// which applies the changes.
+#[wren_class(name = WindowHandle)]
+pub struct WrenWindowHandle(pub Rc<RefCell<WindowControl>>);
+
+#[wren_methods]
+impl WrenWindowHandle {
+    #[construct]
+    fn new_() -> Self {
+        unimplemented!("Window handle must be created from Rust")
+    }
+
+    #[method(name = width_)]
+    fn width(&self) -> f64 {
+        self.0.borrow().size[0]
+    }
+
+    #[method(name = height_)]
+    fn height(&self) -> f64 {
+        self.0.borrow().size[1]
+    }
+
+    #[method(name = x_)]
+    fn x(&self) -> f64 {
+        self.0.borrow().position[0]
+    }
+
+    #[method(name = y_)]
+    fn y(&self) -> f64 {
+        self.0.borrow().position[1]
+    }
+
+    #[method(name = scaleFactor_)]
+    fn scale_factor(&self) -> f64 {
+        self.0.borrow().scale_factor
+    }
+
+    #[method(name = title_)]
+    fn title(&self) -> String {
+        self.0.borrow().title.clone()
+    }
+
+    #[method(name = fullscreen_)]
+    fn fullscreen(&self) -> String {
+        self.0.borrow().fullscreen.name().to_string()
+    }
+
+    #[method(name = isMinimized_)]
+    fn is_minimized(&self) -> bool {
+        self.0.borrow().minimized
+    }
+
+    #[method(name = isMaximized_)]
+    fn is_maximized(&self) -> bool {
+        self.0.borrow().maximized
+    }
+
+    #[method(name = isCursorVisible_)]
+    fn is_cursor_visible(&self) -> bool {
+        self.0.borrow().cursor_visible
+    }
+
+    #[method(name = isCursorGrabbed_)]
+    fn is_cursor_grabbed(&self) -> bool {
+        self.0.borrow().cursor_grabbed
+    }
+
+    #[method(name = monitorCount_)]
+    fn monitor_count(&self) -> f64 {
+        self.0.borrow().monitors.len() as f64
+    }
+
+    #[method(name = currentMonitor_)]
+    fn current_monitor(&self) -> Option<f64> {
+        self.0.borrow().current_monitor.map(|monitor| monitor as f64)
+    }
+
+    #[method(name = primaryMonitor_)]
+    fn primary_monitor(&self) -> Option<f64> {
+        self.0.borrow().primary_monitor.map(|monitor| monitor as f64)
+    }
+
+    #[method(name = monitorName_)]
+    fn monitor_name(&self, monitor: f64) -> Result<String, ForeignError> {
+        self.with_monitor(monitor, |info| info.name.clone())
+    }
+
+    #[method(name = monitorWidth_)]
+    fn monitor_width(&self, monitor: f64) -> Result<f64, ForeignError> {
+        self.with_monitor(monitor, |info| info.size[0] as f64)
+    }
+
+    #[method(name = monitorHeight_)]
+    fn monitor_height(&self, monitor: f64) -> Result<f64, ForeignError> {
+        self.with_monitor(monitor, |info| info.size[1] as f64)
+    }
+
+    #[method(name = monitorX_)]
+    fn monitor_x(&self, monitor: f64) -> Result<f64, ForeignError> {
+        self.with_monitor(monitor, |info| info.position[0] as f64)
+    }
+
+    #[method(name = monitorY_)]
+    fn monitor_y(&self, monitor: f64) -> Result<f64, ForeignError> {
+        self.with_monitor(monitor, |info| info.position[1] as f64)
+    }
+
+    #[method(name = monitorScaleFactor_)]
+    fn monitor_scale_factor(&self, monitor: f64) -> Result<f64, ForeignError> {
+        self.with_monitor(monitor, |info| info.scale_factor)
+    }
+
+    #[method(name = videoModeCount_)]
+    fn video_mode_count(&self, monitor: f64) -> Result<f64, ForeignError> {
+        self.with_monitor(monitor, |info| info.video_modes.len() as f64)
+    }
+
+    #[method(name = videoModeWidth_)]
+    fn video_mode_width(&self, monitor: f64, mode: f64) -> Result<f64, ForeignError> {
+        self.with_video_mode(monitor, mode, |info| info.size[0] as f64)
+    }
+
+    #[method(name = videoModeHeight_)]
+    fn video_mode_height(&self, monitor: f64, mode: f64) -> Result<f64, ForeignError> {
+        self.with_video_mode(monitor, mode, |info| info.size[1] as f64)
+    }
+
+    #[method(name = videoModeBitDepth_)]
+    fn video_mode_bit_depth(&self, monitor: f64, mode: f64) -> Result<f64, ForeignError> {
+        self.with_video_mode(monitor, mode, |info| info.bit_depth as f64)
+    }
+
+    #[method(name = videoModeRefreshRate_)]
+    fn video_mode_refresh_rate(&self, monitor: f64, mode: f64) -> Result<f64, ForeignError> {
+        self.with_video_mode(monitor, mode, |info| info.refresh_rate as f64)
+    }
+
+    #[method(name = setWindowed_)]
+    fn set_windowed(&self) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Windowed)
+    }
+
+    #[method(name = setBorderless_)]
+    fn set_borderless(&self) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Borderless(None))
+    }
+
+    #[method(name = setBorderlessOn_)]
+    fn set_borderless_on(&self, monitor: f64) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Borderless(Some(index(monitor)?)))
+    }
+
+    #[method(name = setExclusive_)]
+    fn set_exclusive(&self, monitor: f64, mode: f64) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Exclusive {
+            monitor: index(monitor)?,
+            mode: index(mode)?,
+        })
+    }
+
+    #[method(name = resize_)]
+    fn resize(&self, width: f64, height: f64) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Resize([width, height]))
+    }
+
+    #[method(name = move_)]
+    fn move_to(&self, x: f64, y: f64) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Move([x, y]))
+    }
+
+    #[method(name = setTitle_)]
+    fn set_title(&self, title: &str) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Title(title.to_string()))
+    }
+
+    #[method(name = setMinimized_)]
+    fn set_minimized(&self, minimized: bool) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Minimize(minimized))
+    }
+
+    #[method(name = setMaximized_)]
+    fn set_maximized(&self, maximized: bool) -> Result<(), ForeignError> {
+        self.push(WindowCommand::Maximize(maximized))
+    }
+
+    #[method(name = setCursorVisible_)]
+    fn set_cursor_visible(&self, visible: bool) -> Result<(), ForeignError> {
+        self.push(WindowCommand::CursorVisible(visible))
+    }
+
+    #[method(name = setCursorGrabbed_)]
+    fn set_cursor_grabbed(&self, grab: bool) -> Result<(), ForeignError> {
+        self.push(WindowCommand::CursorGrab(grab))
+    }
+}
+
+impl WrenWindowHandle {
+    fn push(&self, command: WindowCommand) -> Result<(), ForeignError> {
+        self.0.borrow_mut().push(command).map_err(|err| foreign_error!(err))
+    }
+
+    fn with_monitor<F, R>(&self, monitor: f64, f: F) -> Result<R, ForeignError>
+    where
+        F: FnOnce(&MonitorInfo) -> R,
+    {
+        let control = self.0.borrow();
+        let info = control.monitor(index(monitor)?).map_err(|err| foreign_error!(err))?;
+        Ok(f(info))
+    }
+
+    fn with_video_mode<F, R>(&self, monitor: f64, mode: f64, f: F) -> Result<R, ForeignError>
+    where
+        F: FnOnce(&VideoModeInfo) -> R,
+    {
+        let control = self.0.borrow();
+        let info = control
+            .video_mode(index(monitor)?, index(mode)?)
+            .map_err(|err| foreign_error!(err))?;
+        Ok(f(info))
+    }
+}
+
+fn index(value: f64) -> Result<usize, ForeignError> {
+    parse_index(value).map_err(|err| foreign_error!(err))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn monitor(name: &str, modes: &[[u32; 2]]) -> MonitorInfo {
+        MonitorInfo {
+            name: name.to_string(),
+            size: [1920, 1080],
+            position: [0, 0],
+            scale_factor: 1.0,
+            video_modes: modes
+                .iter()
+                .map(|size| VideoModeInfo {
+                    size: *size,
+                    bit_depth: 32,
+                    refresh_rate: 60,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_push_commands() {
+        let mut control = WindowControl::new();
+        control.monitors = vec![monitor("Primary", &[[1920, 1080], [1280, 720]])];
+
+        control.push(WindowCommand::Resize([800.0, 600.0])).unwrap();
+        control.push(WindowCommand::CursorVisible(false)).unwrap();
+        assert_eq!(control.size, [800.0, 600.0]);
+        assert!(!control.cursor_visible);
+
+        // Rejected without changing the state or queuing anything.
+        assert!(control.push(WindowCommand::Resize([0.0, 600.0])).is_err());
+        assert!(control.push(WindowCommand::Resize([-800.0, 600.0])).is_err());
+        assert!(control.push(WindowCommand::Resize([800.0, f64::NAN])).is_err());
+        assert!(control.push(WindowCommand::Move([f64::INFINITY, 0.0])).is_err());
+        assert!(control.push(WindowCommand::Move([0.0, f64::NAN])).is_err());
+        assert_eq!(control.size, [800.0, 600.0]);
+        assert_eq!(control.position, [0.0, 0.0]);
+
+        control.push(WindowCommand::Move([-10.0, 20.0])).unwrap();
+        assert_eq!(control.position, [-10.0, 20.0]);
+
+        control.push(WindowCommand::Exclusive { monitor: 0, mode: 1 }).unwrap();
+        assert_eq!(control.fullscreen, FullscreenMode::Exclusive);
+        assert_eq!(control.size, [1280.0, 720.0]);
+
+        assert_eq!(
+            control.take_commands(),
+            vec![
+                WindowCommand::Resize([800.0, 600.0]),
+                WindowCommand::CursorVisible(false),
+                WindowCommand::Move([-10.0, 20.0]),
+                WindowCommand::Exclusive { monitor: 0, mode: 1 },
+            ]
+        );
+        assert!(control.take_commands().is_empty());
+    }
+
+    #[test]
+    fn test_push_invalid_monitor() {
+        let mut control = WindowControl::new();
+        control.monitors = vec![monitor("Primary", &[[1920, 1080]])];
+
+        assert!(control.push(WindowCommand::Borderless(Some(1))).is_err());
+        assert!(control.push(WindowCommand::Exclusive { monitor: 0, mode: 1 }).is_err());
+        assert_eq!(control.fullscreen, FullscreenMode::Windowed);
+        assert!(control.take_commands().is_empty());
+    }
+
+    #[test]
+    fn test_parse_index() {
+        assert_eq!(parse_index(2.0).unwrap(), 2);
+        assert!(parse_index(-1.0).is_err());
+        assert!(parse_index(0.5).is_err());
+        assert!(parse_index(f64::NAN).is_err());
+    }
+}
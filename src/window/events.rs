@@ -0,0 +1,118 @@
+//! Window lifecycle events, sent to scripts as `Window` signals.
+use crate::window::WINDOW_MODULE;
+use rust_wren::{handle::WrenCallHandle, WrenContext, WrenResult};
+use winit::event::WindowEvent;
+
+/// Window event in the form the Wren side receives it.
+///
+/// Sizes and positions are logical, like the rest of `Window`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowSignal {
+    Resize([f64; 2]),
+    Focus(bool),
+    ScaleFactorChanged(f64),
+    FileDropped(String),
+    CursorEnter,
+    CursorLeave,
+    Moved([f64; 2]),
+}
+
+impl WindowSignal {
+    /// Returns `None` for events that aren't sent as signals.
+    pub fn from_event(event: &WindowEvent, scale_factor: f64) -> Option<Self> {
+        let signal = match event {
+            WindowEvent::Resized(inner_size) => {
+                let size = inner_size.to_logical::<f64>(scale_factor);
+                WindowSignal::Resize([size.width, size.height])
+            }
+            WindowEvent::Focused(focused) => WindowSignal::Focus(*focused),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => WindowSignal::ScaleFactorChanged(*scale_factor),
+            WindowEvent::DroppedFile(path) => WindowSignal::FileDropped(path.to_string_lossy().into_owned()),
+            WindowEvent::CursorEntered { .. } => WindowSignal::CursorEnter,
+            WindowEvent::CursorLeft { .. } => WindowSignal::CursorLeave,
+            WindowEvent::Moved(position) => {
+                let position = position.to_logical::<f64>(scale_factor);
+                WindowSignal::Moved([position.x, position.y])
+            }
+            _ => return None,
+        };
+
+        Some(signal)
+    }
+
+    /// Calls into the window module with the event.
+    pub fn send(&self, ctx: &mut WrenContext, signals: &WindowSignals) -> WrenResult<()> {
+        match self {
+            WindowSignal::Resize([width, height]) => signals.resize.call::<_, ()>(ctx, (*width, *height)),
+            WindowSignal::Focus(focused) => signals.focus.call::<_, ()>(ctx, *focused),
+            WindowSignal::ScaleFactorChanged(scale_factor) => {
+                signals.scale_factor_changed.call::<_, ()>(ctx, *scale_factor)
+            }
+            WindowSignal::FileDropped(path) => signals.file_dropped.call::<_, ()>(ctx, path.as_str()),
+            WindowSignal::CursorEnter => signals.cursor_enter.call::<_, ()>(ctx, ()),
+            WindowSignal::CursorLeave => signals.cursor_leave.call::<_, ()>(ctx, ()),
+            WindowSignal::Moved([x, y]) => signals.moved.call::<_, ()>(ctx, (*x, *y)),
+        }
+    }
+}
+
+/// Hooks into the `Window` class that emit its signals.
+pub struct WindowSignals {
+    pub resize: WrenCallHandle,
+    pub focus: WrenCallHandle,
+    pub scale_factor_changed: WrenCallHandle,
+    pub file_dropped: WrenCallHandle,
+    pub cursor_enter: WrenCallHandle,
+    pub cursor_leave: WrenCallHandle,
+    pub moved: WrenCallHandle,
+}
+
+impl WindowSignals {
+    pub fn new(ctx: &mut WrenContext) -> WrenResult<Self> {
+        Ok(Self {
+            resize: ctx.make_call_ref(WINDOW_MODULE, "Window", "sendResize_(_,_)")?.leak()?,
+            focus: ctx.make_call_ref(WINDOW_MODULE, "Window", "sendFocus_(_)")?.leak()?,
+            scale_factor_changed: ctx
+                .make_call_ref(WINDOW_MODULE, "Window", "sendScaleFactorChanged_(_)")?
+                .leak()?,
+            file_dropped: ctx
+                .make_call_ref(WINDOW_MODULE, "Window", "sendFileDropped_(_)")?
+                .leak()?,
+            cursor_enter: ctx
+                .make_call_ref(WINDOW_MODULE, "Window", "sendCursorEnter_()")?
+                .leak()?,
+            cursor_leave: ctx
+                .make_call_ref(WINDOW_MODULE, "Window", "sendCursorLeave_()")?
+                .leak()?,
+            moved: ctx.make_call_ref(WINDOW_MODULE, "Window", "sendMoved_(_,_)")?.leak()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+    use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+    #[test]
+    fn test_from_event() {
+        assert_eq!(
+            WindowSignal::from_event(&WindowEvent::Resized(PhysicalSize::new(1600, 1200)), 2.0),
+            Some(WindowSignal::Resize([800.0, 600.0]))
+        );
+        assert_eq!(
+            WindowSignal::from_event(&WindowEvent::Moved(PhysicalPosition::new(20, -40)), 2.0),
+            Some(WindowSignal::Moved([10.0, -20.0]))
+        );
+        assert_eq!(
+            WindowSignal::from_event(&WindowEvent::Focused(false), 1.0),
+            Some(WindowSignal::Focus(false))
+        );
+        assert_eq!(
+            WindowSignal::from_event(&WindowEvent::DroppedFile(PathBuf::from("levels/1.json")), 1.0),
+            Some(WindowSignal::FileDropped("levels/1.json".to_string()))
+        );
+        assert_eq!(WindowSignal::from_event(&WindowEvent::CloseRequested, 1.0), None);
+    }
+}
@@ -1,17 +1,33 @@
 mod config;
+mod control;
+mod events;
 
 pub const WINDOW_MODULE: &str = "gers.window";
 pub use config::WrenWindowConfig;
+pub use control::{WindowCommand, WindowControl, WrenWindowHandle};
+pub use events::{WindowSignal, WindowSignals};
 
-use rust_wren::{prelude::*, ModuleBuilder, WrenResult};
+use crate::io::BuiltinModules;
+use rust_wren::{ModuleBuilder, WrenContext, WrenResult};
+use std::{cell::RefCell, rc::Rc};
 
-pub fn register_window(vm: &mut WrenVm) -> WrenResult<()> {
-    vm.interpret(WINDOW_MODULE, include_str!("config.wren"))?;
-    vm.interpret(WINDOW_MODULE, include_str!("signal.wren"))?;
-
-    Ok(())
+pub fn register_window(builtins: &mut BuiltinModules) {
+    builtins.add(WINDOW_MODULE, include_str!("config.wren"));
+    builtins.add(WINDOW_MODULE, include_str!("signal.wren"));
+    builtins.add(WINDOW_MODULE, include_str!("window.wren"));
 }
 
 pub fn bind_window(module: &mut ModuleBuilder) {
     module.register::<WrenWindowConfig>();
+    module.register::<WrenWindowHandle>();
+}
+
+/// Shares a new window control with the script's `Window` class.
+///
+/// Without a window, changes are only reflected back to scripts.
+pub fn init_window_control(ctx: &mut WrenContext) -> WrenResult<Rc<RefCell<WindowControl>>> {
+    let control = Rc::new(RefCell::new(WindowControl::new()));
+    let set_handle = ctx.make_call_ref(WINDOW_MODULE, "Window", "handle_=(_)")?;
+    set_handle.call::<_, ()>(ctx, WrenWindowHandle(control.clone()))?;
+    Ok(control)
 }